@@ -1,11 +1,12 @@
 use std::fmt::Display;
 
+use axum::body::HttpBody;
 pub use axum::http::Uri;
 pub use axum::middleware;
 pub use axum::{
     async_trait,
     body::Body,
-    extract::{FromRequestParts, Json, Query},
+    extract::{FromRef, FromRequestParts, Json, Query},
     http::header::*,
     http::request::Parts,
     http::StatusCode,
@@ -13,7 +14,10 @@ pub use axum::{
     routing::{self, get, post},
     RequestPartsExt, Router,
 };
-use axum::{extract::Request, middleware::Next};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+};
 pub use axum_extra::headers::Cookie;
 pub use axum_extra::typed_header::TypedHeaderRejection;
 pub use axum_extra::TypedHeader;
@@ -27,15 +31,32 @@ pub mod tokio {
 }
 pub use axum::response::Redirect;
 pub use rizz::{
-    self, and, asc, connection, desc, eq, like, or, r#in, Blob, Connection, Database, Integer,
-    JournalMode, Migrator, Real, Synchronous, Table, Text,
+    self, and, asc, connection, desc, eq, gte, is_not_null, is_null, like, lt, ne, or, r#in, Blob,
+    Connection, Database, Integer, JournalMode, Migrator, Real, Synchronous, Table, Text,
 };
 pub use serde::*;
 
+#[cfg(any(test, feature = "test"))]
+pub mod test;
+
 pub fn ulid() -> String {
     ulid::Ulid::new().to_string()
 }
 
+/// `left like '%needle%'` with the needle's `%` and `_` matched as
+/// themselves, rizz's `like` has no escape clause so those are wildcards
+pub fn contains(left: impl rizz::ToColumn, needle: &str) -> rizz::Sql {
+    let needle = needle
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    rizz::Sql {
+        clause: format!("{} like ? escape '\\'", left.to_column()),
+        params: vec![format!("%{}%", needle).into()],
+    }
+}
+
 pub mod html {
     use std::fmt::Display;
 
@@ -45,10 +66,10 @@ pub mod html {
     use axum::response::{IntoResponse, Response};
     pub use stpl::html::RenderExt;
     pub use stpl::html::{
-        a, b, blockquote, body, button, datalist, div, doctype, footer, form, h1, h2, h3, h4, h5,
-        head, html, i, img, input, label, li, link, main, meta, nav, ol, option, p, pre, raw,
-        script, section, span, string, tbody, textarea, th, thead, tr, tt, u, ul, BareTag,
-        FinalTag, Tag,
+        a, b, blockquote, body, button, datalist, details, div, doctype, footer, form, h1, h2, h3,
+        h4, h5, head, html, i, img, input, label, li, link, main, meta, nav, ol, option, p, pre,
+        raw, script, section, select, span, string, summary, table, tbody, td, textarea, th, thead,
+        tr, tt, u, ul, BareTag, FinalTag, Tag,
     };
     pub use stpl::Render;
     pub use stpl::Renderer;
@@ -76,18 +97,40 @@ pub mod html {
     }
 }
 
-pub struct App {
-    router: Router,
+pub type ErrorPage = std::sync::Arc<dyn Fn(StatusCode) -> Response + Send + Sync>;
+
+/// An app around router state `S`, which extractors reach through
+/// `FromRef<S>` and which `router()` hands over with `Router::with_state`.
+pub struct App<S> {
+    router: Router<S>,
+    state: S,
+    error_page: ErrorPage,
+    security_headers: Option<SecurityHeaders>,
 }
 
-pub fn app() -> App {
+pub fn app<S: Clone + Send + Sync + 'static>(state: S) -> App<S> {
     App {
         router: Router::new(),
+        state,
+        error_page: std::sync::Arc::new(default_error_page),
+        security_headers: None,
     }
 }
 
-impl App {
-    pub fn routes(mut self, router: Router) -> Self {
+fn default_error_page(status_code: StatusCode) -> Response {
+    (
+        status_code,
+        [
+            (CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (CACHE_CONTROL, "no-store"),
+        ],
+        status_code.canonical_reason().unwrap_or("error"),
+    )
+        .into_response()
+}
+
+impl<S: Clone + Send + Sync + 'static> App<S> {
+    pub fn routes(mut self, router: Router<S>) -> Self {
         self.router = router;
         self
     }
@@ -104,28 +147,217 @@ impl App {
                             (CACHE_CONTROL, "public, max-age=604800, immutable"),
                         ],
                         file.content,
-                    ),
-                    None => (
-                        StatusCode::NOT_FOUND,
-                        [
-                            (CONTENT_TYPE, "text/html; charset=utf-8"),
-                            (CACHE_CONTROL, "public, max-age=604800, immutable"),
-                        ],
-                        "not found".as_bytes().to_vec(),
-                    ),
+                    )
+                        .into_response(),
+                    None => StatusCode::NOT_FOUND.into_response(),
                 }
             }),
         );
         self
     }
 
+    /// Renders the body of any error response that a handler left empty,
+    /// including unknown routes and missing static files.
+    pub fn error_page(
+        mut self,
+        error_page: impl Fn(StatusCode) -> Response + Send + Sync + 'static,
+    ) -> Self {
+        self.error_page = std::sync::Arc::new(error_page);
+        self
+    }
+
+    pub fn security_headers(mut self, security_headers: SecurityHeaders) -> Self {
+        self.security_headers = Some(security_headers);
+        self
+    }
+
+    pub fn router(self) -> Router {
+        let router = self
+            .router
+            .fallback(|| async { StatusCode::NOT_FOUND.into_response() })
+            .with_state(self.state)
+            .layer(middleware::from_fn_with_state(
+                self.error_page,
+                error_page_middleware,
+            ));
+        match self.security_headers {
+            Some(security_headers) => router.layer(middleware::from_fn_with_state(
+                std::sync::Arc::new(security_headers),
+                security_headers_middleware,
+            )),
+            None => router,
+        }
+    }
+
     pub async fn serve(self, ip: &str) {
         let listener = tokio::net::TcpListener::bind(ip).await.unwrap();
         println!("Listening on {}", ip);
-        axum::serve(listener, self.router).await.unwrap();
+        axum::serve(listener, self.router()).await.unwrap();
     }
 }
 
+async fn error_page_middleware(
+    State(error_page): State<ErrorPage>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let status_code = response.status();
+    let is_error = status_code.is_client_error() || status_code.is_server_error();
+    match is_error && response.body().size_hint().exact() == Some(0) {
+        true => error_page(status_code),
+        false => response,
+    }
+}
+
+tokio::task_local! {
+    static NONCE: String;
+}
+
+/// The CSP nonce for the current request. Inline scripts need
+/// `script.nonce(nonce())` once [`App::security_headers`] is on.
+pub fn nonce() -> String {
+    NONCE.try_with(|nonce| nonce.clone()).unwrap_or_default()
+}
+
+#[derive(Clone)]
+pub struct SecurityHeaders {
+    origin: Option<String>,
+    scripts: Vec<String>,
+    styles: Vec<String>,
+    frame_options: &'static str,
+    referrer_policy: &'static str,
+    permissions_policy: &'static str,
+    hsts_max_age: Option<u64>,
+}
+
+pub fn security_headers() -> SecurityHeaders {
+    SecurityHeaders::default()
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self {
+            origin: None,
+            scripts: vec![],
+            styles: vec![],
+            frame_options: "DENY",
+            referrer_policy: "same-origin",
+            permissions_policy: "camera=(), microphone=(), geolocation=(), payment=()",
+            #[cfg(debug_assertions)]
+            hsts_max_age: None,
+            #[cfg(not(debug_assertions))]
+            hsts_max_age: Some(31_536_000),
+        }
+    }
+}
+
+impl SecurityHeaders {
+    /// CSP host sources need a host, so the script and style urls are only
+    /// whitelisted one by one when the public origin is known. Without it
+    /// they fall back to `'self'`.
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into().trim_end_matches('/').to_owned());
+        self
+    }
+
+    pub fn script(mut self, url: impl Into<std::borrow::Cow<'static, str>>) -> Self {
+        self.scripts.push(url.into().into_owned());
+        self
+    }
+
+    pub fn style(mut self, url: impl Into<std::borrow::Cow<'static, str>>) -> Self {
+        self.styles.push(url.into().into_owned());
+        self
+    }
+
+    pub fn frame_options(mut self, frame_options: &'static str) -> Self {
+        self.frame_options = frame_options;
+        self
+    }
+
+    pub fn referrer_policy(mut self, referrer_policy: &'static str) -> Self {
+        self.referrer_policy = referrer_policy;
+        self
+    }
+
+    pub fn permissions_policy(mut self, permissions_policy: &'static str) -> Self {
+        self.permissions_policy = permissions_policy;
+        self
+    }
+
+    pub fn hsts(mut self, max_age: Option<u64>) -> Self {
+        self.hsts_max_age = max_age;
+        self
+    }
+
+    fn sources(&self, urls: &[String]) -> String {
+        match &self.origin {
+            Some(origin) if !urls.is_empty() => urls
+                .iter()
+                .map(|url| format!("{}{}", origin, url))
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => "'self'".to_owned(),
+        }
+    }
+
+    fn content_security_policy(&self, nonce: &str) -> String {
+        vec![
+            "default-src 'self'".to_owned(),
+            format!(
+                "script-src {} 'nonce-{}'",
+                self.sources(&self.scripts),
+                nonce
+            ),
+            format!("style-src {} 'nonce-{}'", self.sources(&self.styles), nonce),
+            "img-src 'self' data:".to_owned(),
+            "connect-src 'self'".to_owned(),
+            "object-src 'none'".to_owned(),
+            "base-uri 'self'".to_owned(),
+            "form-action 'self'".to_owned(),
+            "frame-ancestors 'none'".to_owned(),
+        ]
+        .join("; ")
+    }
+}
+
+async fn security_headers_middleware(
+    State(security_headers): State<std::sync::Arc<SecurityHeaders>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let nonce = ulid();
+    let mut response = NONCE.scope(nonce.clone(), next.run(request)).await;
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_SECURITY_POLICY,
+        HeaderValue::from_str(&security_headers.content_security_policy(&nonce)).unwrap(),
+    );
+    headers.insert(
+        X_FRAME_OPTIONS,
+        HeaderValue::from_static(security_headers.frame_options),
+    );
+    headers.insert(
+        REFERRER_POLICY,
+        HeaderValue::from_static(security_headers.referrer_policy),
+    );
+    headers.insert(
+        PERMISSIONS_POLICY,
+        HeaderValue::from_static(security_headers.permissions_policy),
+    );
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    if let Some(max_age) = security_headers.hsts_max_age {
+        headers.insert(
+            STRICT_TRANSPORT_SECURITY,
+            HeaderValue::from_str(&format!("max-age={}; includeSubDomains", max_age)).unwrap(),
+        );
+    }
+    response
+}
+
+const PERMISSIONS_POLICY: HeaderName = HeaderName::from_static("permissions-policy");
+
 pub async fn etag_middleware(request: Request, next: Next) -> Response {
     let if_none_match_header = request.headers().get(IF_NONE_MATCH).cloned();
     let response = next.run(request).await;
@@ -153,6 +385,43 @@ pub async fn etag_middleware(request: Request, next: Next) -> Response {
     }
 }
 
+/// What htmx told us about the request
+pub struct Htmx {
+    pub request: bool,
+    pub boosted: bool,
+}
+
+impl Htmx {
+    /// An `hx-get` or `hx-post` swapping into part of the page, where a full
+    /// page render or a redirect would be the wrong answer
+    pub fn targeted(&self) -> bool {
+        self.request && !self.boosted
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Htmx
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let is_true = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .map(|value| value == "true")
+                .unwrap_or(false)
+        };
+
+        Ok(Htmx {
+            request: is_true("hx-request"),
+            boosted: is_true("hx-boosted"),
+        })
+    }
+}
+
 pub fn res() -> Responder {
     Responder::new()
 }
@@ -191,6 +460,11 @@ impl Responder {
         self
     }
 
+    pub fn status(mut self, status_code: StatusCode) -> Self {
+        self.status_code = status_code;
+        self
+    }
+
     pub fn cache(mut self, cache: Cache) -> Self {
         self.headers
             .insert(CACHE_CONTROL, cache.to_string().parse().unwrap());
@@ -0,0 +1,236 @@
+use crate::{
+    ulid, HeaderMap, HeaderValue, Router, StatusCode, CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE,
+};
+use axum::body::Body;
+use axum::http::{Method, Request};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tower::ServiceExt;
+
+const HX_LOCATION: &str = "hx-location";
+const MAX_REDIRECTS: usize = 10;
+
+/// A sqlite file in the system temp dir that is removed, along with its
+/// wal and shm files, when dropped.
+pub struct TempDatabase {
+    path: PathBuf,
+}
+
+impl Default for TempDatabase {
+    fn default() -> Self {
+        Self {
+            path: std::env::temp_dir().join(format!("dubs-{}.sqlite3", ulid())),
+        }
+    }
+}
+
+impl TempDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.to_str().expect("temp dir is not valid utf-8")
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path(), suffix));
+        }
+    }
+}
+
+/// Sends requests straight to a router without binding a port, keeping
+/// cookies between requests and following redirects like a browser would.
+pub struct TestClient {
+    router: Router,
+    cookies: BTreeMap<String, String>,
+    htmx: bool,
+}
+
+impl TestClient {
+    pub fn new(router: Router) -> Self {
+        Self {
+            router,
+            cookies: BTreeMap::new(),
+            htmx: false,
+        }
+    }
+
+    /// Sends `HX-Request: true` with every request from now on.
+    pub fn htmx(mut self) -> Self {
+        self.htmx = true;
+        self
+    }
+
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies.get(name).map(|value| value.as_str())
+    }
+
+    pub async fn get(&mut self, uri: &str) -> TestResponse {
+        self.request(Method::GET, uri, None).await
+    }
+
+    /// Posts `json` the same way the json-enc htmx extension does.
+    pub async fn post(&mut self, uri: &str, json: impl Serialize) -> TestResponse {
+        let body = serde_json::to_string(&json).expect("could not serialize request body");
+        self.request(Method::POST, uri, Some(body)).await
+    }
+
+    async fn request(&mut self, method: Method, uri: &str, body: Option<String>) -> TestResponse {
+        let mut response = self.send(method, uri, body).await;
+        let mut redirects = vec![];
+        while response.status.is_redirection() && redirects.len() < MAX_REDIRECTS {
+            let Some(location) = response.location().map(|location| location.to_owned()) else {
+                break;
+            };
+            redirects.push(location.clone());
+            response = self.send(Method::GET, &location, None).await;
+        }
+        response.redirects = redirects;
+
+        response
+    }
+
+    async fn send(&mut self, method: Method, uri: &str, body: Option<String>) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        if !self.cookies.is_empty() {
+            let cookie = self
+                .cookies
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join("; ");
+            request = request.header(COOKIE, cookie);
+        }
+        if self.htmx {
+            request = request.header("hx-request", "true");
+        }
+        let request = match body {
+            Some(body) => request
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body)),
+            None => request.body(Body::empty()),
+        }
+        .expect("could not build request");
+
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("router is infallible");
+        let (parts, body) = response.into_parts();
+        for value in parts.headers.get_all(SET_COOKIE) {
+            self.set_cookie(value);
+        }
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .expect("could not read response body");
+
+        TestResponse {
+            uri: uri.to_owned(),
+            status: parts.status,
+            headers: parts.headers,
+            body: String::from_utf8_lossy(&bytes).into_owned(),
+            redirects: vec![],
+        }
+    }
+
+    fn set_cookie(&mut self, value: &HeaderValue) {
+        let Ok(value) = value.to_str() else {
+            return;
+        };
+        let mut attributes = value.split(';').map(|part| part.trim());
+        let Some((name, value)) = attributes.next().and_then(|pair| pair.split_once('=')) else {
+            return;
+        };
+        let expired = value.is_empty()
+            || attributes.any(|attribute| attribute.eq_ignore_ascii_case("max-age=0"));
+        match expired {
+            true => self.cookies.remove(name),
+            false => self.cookies.insert(name.to_owned(), value.to_owned()),
+        };
+    }
+}
+
+pub struct TestResponse {
+    /// The uri of the last request, after following redirects
+    pub uri: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+    /// Every `HX-Location` or `Location` that was followed to get here
+    pub redirects: Vec<String>,
+}
+
+impl TestResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// `HX-Location` wins over `Location`, same as in htmx
+    pub fn location(&self) -> Option<&str> {
+        self.header(HX_LOCATION)
+            .or_else(|| self.header(LOCATION.as_str()))
+    }
+
+    pub fn assert_status(&self, status: StatusCode) -> &Self {
+        assert_eq!(
+            self.status, status,
+            "{} responded with {}:\n{}",
+            self.uri, self.status, self.body
+        );
+        self
+    }
+
+    pub fn assert_redirected_to(&self, uri: &str) -> &Self {
+        assert_eq!(
+            self.redirects.last().map(|redirect| redirect.as_str()),
+            Some(uri),
+            "expected {} to redirect to {}, redirects: {:?}",
+            self.uri,
+            uri,
+            self.redirects
+        );
+        self
+    }
+
+    pub fn assert_contains(&self, html: &str) -> &Self {
+        assert!(
+            self.body.contains(html),
+            "expected {} to contain {:?}:\n{}",
+            self.uri,
+            html,
+            self.body
+        );
+        self
+    }
+
+    pub fn assert_not_contains(&self, html: &str) -> &Self {
+        assert!(
+            !self.body.contains(html),
+            "expected {} not to contain {:?}:\n{}",
+            self.uri,
+            html,
+            self.body
+        );
+        self
+    }
+
+    /// The `value` of the first `<input name="{name}">`, handy for pulling
+    /// hidden ids out of a rendered form.
+    pub fn input_value(&self, name: &str) -> Option<String> {
+        self.body
+            .split("<input")
+            .skip(1)
+            .filter_map(|input| input.split_once('>').map(|(attributes, _)| attributes))
+            .find(|attributes| attributes.contains(&format!("name=\"{}\"", name)))
+            .and_then(|attributes| attributes.split_once("value=\""))
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(value, _)| value.to_owned())
+    }
+}
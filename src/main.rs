@@ -16,35 +16,74 @@ mod frontend {
 #[cfg(feature = "backend")]
 mod backend {
     use axum::{middleware, Router};
-    use db::{db, Database, Session, Set, User};
+    use date::Tz;
+    use db::{
+        Database, Exercise, Load, LoggedSet, Measurement, MeasurementKind, Metrics, MuscleGroup,
+        Program, Rest, Routine, Session, Set, SetDeletedAt, SetType, Template, TemplateExercise,
+        TemplateSettings, TrainingDay, TrainingStats, User, Week,
+    };
     use dubs::html::RenderExt;
     use dubs::{
-        and, app, asc, async_trait, desc, eq, etag_middleware, res, tokio, Cookie, Css,
-        FromRequestParts, HeaderValue, IntoResponse, Js, Json, JustError, Parts, Responder,
-        Response, StaticFiles, StatusCode, TypedHeader,
+        and, asc, async_trait, contains, desc, eq, etag_middleware, gte, is_not_null, is_null, lt,
+        ne, res, tokio, App, Cookie, Css, FromRef, FromRequestParts, HeaderName, HeaderValue, Htmx,
+        IntoResponse, Js, Json, JustError, Parts, Query, Responder, Response, SecurityHeaders,
+        StaticFiles, StatusCode, TypedHeader, CACHE_CONTROL, CONTENT_DISPOSITION,
     };
     use dubs::{thiserror, ulid};
     use enum_router::Routes;
     use parts::*;
+    use plates::Plate;
     use serde::{Deserialize, Serialize};
     use std::borrow::Cow;
 
     #[tokio::main]
     pub async fn main() -> Result<()> {
-        db().await;
-        app()
-            .routes(routes())
-            .static_files(StaticFile::once())
-            .serve("127.0.0.1:9005")
-            .await;
+        let args = std::env::args().skip(1).collect::<Vec<_>>();
+        let cli = match cli::parse(&args) {
+            Ok(cli) => cli,
+            Err(err) => {
+                eprintln!("{}\n\n{}", err, cli::USAGE);
+                std::process::exit(2);
+            }
+        };
+        if let Err(err) = cli::run(cli).await {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
 
         Ok(())
     }
 
-    fn routes() -> Router {
+    /// Everything but the listener, so `dubs::test::TestClient` can drive the
+    /// same router in process against a `dubs::test::TempDatabase`:
+    /// `TestClient::new(app(Database::open(temp.path()).await?).router())`
+    pub fn app(db: Database) -> App<Database> {
+        dubs::app(db)
+            .routes(routes())
+            .static_files(StaticFile::once())
+            .error_page(error_page)
+            .security_headers(security_headers())
+    }
+
+    fn routes() -> Router<Database> {
         Route::router().layer(middleware::from_fn(etag_middleware))
     }
 
+    fn security_headers() -> SecurityHeaders {
+        let static_files = StaticFile::once();
+        let security_headers = dubs::security_headers()
+            .script(static_files.htmx.clone())
+            .script(static_files.json_enc.clone())
+            .script(static_files.preload.clone())
+            .script(static_files.rest_timer.clone())
+            .script(static_files.time_zone.clone())
+            .style(static_files.tailwind.clone());
+        match std::env::var("ORIGIN") {
+            Ok(origin) => security_headers.origin(origin),
+            Err(_) => security_headers,
+        }
+    }
+
     type Html = Result<Responder>;
 
     async fn root(SomeUser(user): SomeUser) -> impl IntoResponse {
@@ -55,48 +94,185 @@ mod backend {
         response(Route::Root, root_view()).short_cache()
     }
 
-    async fn set_form(user: User) -> Html {
-        let Database { db, sets, .. } = db().await;
+    #[derive(Deserialize)]
+    struct SetFormParams {
+        template: Option<String>,
+        #[serde(default)]
+        step: usize,
+    }
+
+    async fn set_form(database: Database, user: User, Query(params): Query<SetFormParams>) -> Html {
+        let Database { ref db, sets, .. } = database;
         let sets: Vec<Set> = db
             .select()
             .from(sets)
-            .r#where(eq(sets.user_id, &user.id))
+            .r#where(and(eq(sets.user_id, &user.id), is_null(sets.deleted_at)))
             .limit(30)
             .order(vec![asc(sets.name)])
             .all()
             .await?;
         let mut names = sets.into_iter().map(|s| s.name).collect::<Vec<_>>();
         names.dedup();
+        let routine = match params.template {
+            Some(id) => Some(database.routine(&user, &id, params.step).await?),
+            None => None,
+        };
+        let (exercise, form) = match routine.as_ref().and_then(|routine| routine.current()) {
+            Some(current) => (
+                database.exercise(&user, &current.name).await?,
+                SetForm {
+                    name: current.name.clone(),
+                    reps: current.reps,
+                    weight: current.weight,
+                    template: Some(current.template_id.clone()),
+                    step: routine
+                        .as_ref()
+                        .map(|routine| routine.step)
+                        .unwrap_or_default(),
+                    ..Default::default()
+                },
+            ),
+            // pick up where the last set left off so the next one is a tap
+            None => match database.last_set(&user, None).await? {
+                Some(set) => (
+                    database.exercise(&user, &set.name).await?,
+                    SetForm::from(set),
+                ),
+                None => (Exercise::default(), SetForm::default()),
+            },
+        };
+
+        let rest = database.rest(&user).await?;
+
+        // no short cache, the form is pre-filled from the latest set
+        render(
+            Route::SetForm,
+            (
+                rest_timer(rest),
+                set_form_view(names, &exercise, form, routine),
+            ),
+        )
+    }
+
+    fn routine_url(template_id: &str, step: usize) -> String {
+        format!("{}?template={}&step={}", Route::SetForm, template_id, step)
+    }
+
+    #[derive(Deserialize)]
+    struct SetFieldsParams {
+        #[serde(default)]
+        name: String,
+    }
+
+    /// Swapped into the set form when the exercise changes so only the
+    /// inputs that exercise uses are shown
+    async fn set_fields(
+        db: Database,
+        user: Option<User>,
+        Query(params): Query<SetFieldsParams>,
+    ) -> Html {
+        let (exercise, form) = match user {
+            Some(user) => (
+                db.exercise(&user, &params.name).await?,
+                db.last_set(&user, Some(&params.name))
+                    .await?
+                    .map(SetForm::from)
+                    .unwrap_or_default(),
+            ),
+            None => (Exercise::default(), SetForm::default()),
+        };
+
+        Ok(res().render(set_fields_part(&exercise, form)))
+    }
+
+    async fn exercises(db: Database, user: User) -> Html {
+        let exercises = db.exercises(&user).await?;
+
+        render(Route::Exercises, exercises_part(exercises))
+    }
 
-        Ok(response(Route::SetForm, set_form_view(names, SetForm::default())).short_cache())
+    #[derive(Serialize, Deserialize)]
+    struct ExerciseForm {
+        name: String,
+        metrics: Metrics,
+        #[serde(default)]
+        load: Load,
+        #[serde(default, deserialize_with = "de::optional")]
+        rest: Option<usize>,
+        #[serde(default, deserialize_with = "de::optional")]
+        muscle_group: Option<MuscleGroup>,
+    }
+
+    async fn update_exercise(
+        db: Database,
+        user: User,
+        Json(form): Json<ExerciseForm>,
+    ) -> Result<impl IntoResponse> {
+        let mut exercise = db.exercise(&user, &form.name).await?;
+        exercise.metrics = form.metrics;
+        exercise.load = form.load;
+        exercise.rest = form.rest;
+        exercise.muscle_group = form.muscle_group;
+        db.save_exercise(exercise).await?;
+
+        Ok(res().redirect(Route::Exercises))
     }
 
     async fn create_set(
+        database: Database,
         user: Option<User>,
         Json(form): Json<SetForm>,
     ) -> Result<impl IntoResponse> {
         let Database {
-            db,
+            ref db,
             sessions,
             users,
             sets,
-        } = db().await;
-
+            ..
+        } = database;
         match user {
             Some(user) => {
                 // already logged in
                 // create set
+                let template = form.template.clone();
+                let step = form.step;
                 let _: Set = db
                     .insert(sets)
-                    .values(Set::new(&user, form.name.clone(), form.reps, form.weight))?
+                    .values(Set::new(&user, form))?
                     .returning()
                     .await?;
 
-                Ok(res().redirect(Route::SetList))
+                // working through a template, stay on this exercise until
+                // its target sets are done then move on to the next one
+                let redirect = match template {
+                    Some(id) => {
+                        let routine = database.routine(&user, &id, step).await?;
+                        match routine.current() {
+                            Some(current) if routine.done < current.sets => {
+                                routine_url(&id, routine.step)
+                            }
+                            _ if routine.step + 1 < routine.exercises.len() => {
+                                routine_url(&id, routine.step + 1)
+                            }
+                            _ => Route::SetList.to_string(),
+                        }
+                    }
+                    None => Route::SetList.to_string(),
+                };
+
+                Ok(res().redirect(redirect))
             }
             None => {
                 // create user
-                let user: User = db.insert(users).values(User::new())?.returning().await?;
+                let mut user = User::new();
+                if let Some(tz) = form
+                    .time_zone
+                    .as_deref()
+                    .and_then(|tz| tz.parse::<Tz>().ok())
+                {
+                    user.time_zone = tz.name().to_owned();
+                }
+                let user: User = db.insert(users).values(user)?.returning().await?;
 
                 // create session
                 let session: Session = db
@@ -108,654 +284,5752 @@ mod backend {
                 // create set
                 let _: Set = db
                     .insert(sets)
-                    .values(Set::new(&user, form.name, form.reps, form.weight))?
+                    .values(Set::new(&user, form))?
                     .returning()
                     .await?;
 
-                let sets: Vec<Set> = db
-                    .select()
-                    .from(sets)
-                    .r#where(eq(sets.user_id, &user.id))
-                    .order(vec![desc(sets.created_at)])
-                    .limit(30)
-                    .all()
-                    .await?;
+                let sets = database.recent_sets(&user).await?;
 
                 Ok(res()
                     .redirect(Route::SetList)
                     .set_cookie(session_cookie(Some(session.id)))
-                    .render(set_list_part(user, sets)))
+                    .render(set_list_part(user, sets, None, SetFilter::default())))
             }
         }
     }
 
-    async fn set_list(user: User) -> Html {
-        let Database { db, sets, .. } = db().await;
+    #[derive(Deserialize)]
+    struct SetListParams {
+        undo: Option<String>,
+        #[serde(flatten)]
+        filter: SetFilter,
+    }
 
-        let sets: Vec<Set> = db
-            .select()
-            .from(sets)
-            .r#where(eq(sets.user_id, &user.id))
-            .order(vec![desc(sets.created_at)])
-            .limit(30)
-            .all()
-            .await?;
+    /// The set list's filter bar, every field is a query param so a
+    /// filtered list can be shared as a link
+    #[derive(Deserialize, Default, Clone)]
+    struct SetFilter {
+        /// matches part of the exercise name
+        #[serde(default, deserialize_with = "de::optional")]
+        name: Option<String>,
+        /// yyyy-mm-dd, both ends included, the heatmap links here
+        #[serde(default, deserialize_with = "de::optional")]
+        from: Option<String>,
+        #[serde(default, deserialize_with = "de::optional")]
+        to: Option<String>,
+        /// kept as typed, a weight that doesn't parse is ignored like a bad date
+        #[serde(default, deserialize_with = "de::optional")]
+        min_weight: Option<String>,
+        #[serde(default, deserialize_with = "de::optional")]
+        set_type: Option<SetType>,
+    }
 
-        render(Route::SetList, set_list_part(user, sets))
+    impl SetFilter {
+        fn is_empty(&self) -> bool {
+            self.name.is_none()
+                && self.from.is_none()
+                && self.to.is_none()
+                && self.min_weight.is_none()
+                && self.set_type.is_none()
+        }
     }
 
-    async fn profile(user: User) -> impl IntoResponse {
-        response(Route::Profile, profile_part(user)).short_cache()
+    /// Most sets a search shows, the unfiltered list shows the latest 30
+    const SEARCH_LIMIT: usize = 200;
+
+    /// Weeks shown on the stats page
+    const STATS_WEEKS: i64 = 12;
+
+    async fn stats(db: Database, user: User) -> Html {
+        let stats = db.training_stats(&user, STATS_WEEKS).await?;
+        let today = date::today(user.tz());
+        let days = db.training_days(&user, heatmap_start(today)).await?;
+
+        render(Route::Stats, (stats_part(stats), heatmap(days, today)))
     }
 
-    async fn logout() -> impl IntoResponse {
-        res().redirect(Route::Root).set_cookie(session_cookie(None))
+    async fn templates(db: Database, user: User) -> Html {
+        let templates = db.templates(&user).await?;
+
+        render(Route::Templates, templates_part(templates))
     }
 
     #[derive(Serialize, Deserialize)]
-    struct DeleteSetForm {
-        id: String,
+    struct TemplateForm {
+        name: String,
     }
 
-    async fn delete_set(
+    async fn create_template(
+        Database { db, templates, .. }: Database,
         user: User,
-        Json(DeleteSetForm { id }): Json<DeleteSetForm>,
+        Json(form): Json<TemplateForm>,
     ) -> Result<impl IntoResponse> {
-        let Database { db, sets, .. } = db().await;
-        let _ = db
-            .delete_from(sets)
-            .r#where(and(eq(sets.id, id), eq(sets.user_id, user.id)))
-            .rows_affected()
+        let template: Template = db
+            .insert(templates)
+            .values(Template::new(&user, form.name.trim()))?
+            .returning()
             .await?;
 
-        Ok(res().redirect(Route::SetList))
+        Ok(res().redirect(template_url(&template.id)))
     }
 
-    async fn login_form() -> Html {
-        render(
-            Route::Login,
-            login_form_part(LoginForm {
-                secret: "".into(),
-                error: None,
-            }),
-        )
+    fn template_url(id: &str) -> String {
+        format!("{}?id={}", Route::EditTemplate, id)
     }
 
-    #[derive(Serialize, Deserialize, Clone)]
-    struct LoginForm {
-        secret: String,
-        error: Option<String>,
+    fn program_url(template_id: &str) -> String {
+        format!("{}?template={}", Route::Program, template_id)
     }
 
-    async fn login(Json(params): Json<LoginForm>) -> Result<impl IntoResponse> {
-        let Database {
-            db,
-            users,
-            sessions,
-            ..
-        } = db().await;
-        let user: Option<User> = db
-            .select()
-            .from(users)
-            .r#where(eq(users.secret, &params.secret))
-            .first()
-            .await
-            .ok();
-        match user {
-            Some(user) => {
-                let session: Session = db
-                    .insert(sessions)
-                    .values(Session::new(&user))?
-                    .returning()
-                    .await?;
-                Ok(res()
-                    .redirect(Route::Root)
-                    .set_cookie(session_cookie(Some(session.id)))
-                    .into_response())
-            }
-            None => Ok(render(Route::LoginForm, login_form_part(params.clone())).into_response()),
+    /// Fixed templates step through the set form, programs get a checklist
+    fn start_url(template: &Template) -> String {
+        match template.program {
+            Program::Fixed => routine_url(&template.id, 0),
+            _ => program_url(&template.id),
         }
     }
 
-    mod parts {
+    #[derive(Deserialize)]
+    struct ProgramParams {
+        template: String,
+    }
 
-        use super::*;
-        use dubs::{
-            html::{self, *},
-            Responder,
-        };
+    async fn program(db: Database, user: User, Query(params): Query<ProgramParams>) -> Html {
+        let (template, exercises) = db.template(&user, &params.template).await?;
+        let tz = user.tz();
+        let today = date::start_of(date::today(tz), tz);
+        let step = plates::step(&user.plates());
+        let mut checklist = vec![];
+        for exercise in exercises {
+            let sets = db
+                .sets_named(&user, &exercise.name, exercise.created_at)
+                .await?;
+            let (before, logged_today): (Vec<Set>, Vec<Set>) =
+                sets.into_iter().partition(|set| set.performed_at < today);
+            let done = logged_today
+                .iter()
+                .filter(|set| set.set_type.counts())
+                .count();
+            checklist.push((
+                program::prescribe(template.program, exercise, &before, tz, step),
+                done,
+            ));
+        }
 
-        pub trait Render = dubs::html::Render + 'static;
+        render(Route::Program, program_part(template, checklist))
+    }
 
-        pub fn root_view() -> impl Render {
-            div.class("flex flex-col gap-8")((
-                h1.class("text-2xl text-center")("u lift bro?"),
-                set_form_view(vec![], SetForm::default()),
-                a.class("text-center").href(Route::LoginForm)("Already have an account?"),
-            ))
-        }
+    #[derive(Serialize, Deserialize)]
+    struct CheckSetForm {
+        template_id: String,
+        name: String,
+        #[serde(default)]
+        reps: usize,
+        #[serde(default)]
+        weight: f64,
+        #[serde(default)]
+        set_type: SetType,
+    }
 
-        pub fn set_form_view(
-            names: Vec<String>,
-            SetForm { name, reps, weight }: SetForm,
-        ) -> impl Render {
-            form(Route::CreateSet).class("flex flex-col px-4 lg:px-0 pt-4 gap-4")((
-                div((label("exercise"), suggest_input("name", names, name, true))),
-                div.class("flex gap-4")((
-                    div.class("w-full")((label("reps"), number_input("reps", reps))),
-                    div.class("w-full")((label("weight"), number_input("weight", weight))),
-                )),
-                button()("save your set"),
+    /// Ticks a prescribed set off the checklist by logging it
+    async fn check_set(
+        Database { db, sets, .. }: Database,
+        user: User,
+        Json(form): Json<CheckSetForm>,
+    ) -> Result<impl IntoResponse> {
+        let CheckSetForm {
+            template_id,
+            name,
+            reps,
+            weight,
+            set_type,
+        } = form;
+        let _ = db
+            .insert(sets)
+            .values(Set::new(
+                &user,
+                SetForm {
+                    name,
+                    reps,
+                    weight,
+                    set_type,
+                    ..Default::default()
+                },
+            ))?
+            .rows_affected()
+            .await?;
+
+        Ok(res().redirect(program_url(&template_id)))
+    }
+
+    #[derive(Deserialize)]
+    struct TemplateParams {
+        id: String,
+    }
+
+    async fn edit_template(db: Database, user: User, Query(params): Query<TemplateParams>) -> Html {
+        let (template, exercises) = db.template(&user, &params.id).await?;
+        let names = db
+            .exercises(&user)
+            .await?
+            .into_iter()
+            .map(|exercise| exercise.name)
+            .collect();
+
+        render(
+            Route::EditTemplate,
+            edit_template_part(template, exercises, names),
+        )
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct UpdateTemplateForm {
+        id: String,
+        name: String,
+        #[serde(default)]
+        program: Program,
+    }
+
+    async fn update_template(
+        Database { db, templates, .. }: Database,
+        user: User,
+        Json(form): Json<UpdateTemplateForm>,
+    ) -> Result<impl IntoResponse> {
+        let _ = db
+            .update(templates)
+            .set(TemplateSettings {
+                name: form.name.trim().to_owned(),
+                program: form.program,
+            })?
+            .r#where(and(
+                eq(templates.id, &form.id),
+                eq(templates.user_id, &user.id),
             ))
-        }
+            .rows_affected()
+            .await?;
 
-        fn render_if(is_true: bool, part: impl Render) -> impl Render {
-            raw(if is_true {
-                part.render_to_string()
-            } else {
-                String::with_capacity(0)
-            })
-        }
+        Ok(res().redirect(template_url(&form.id)))
+    }
 
-        fn time_ago(seconds: u64) -> impl Render {
-            let now = now();
-            let seconds = now - seconds;
+    #[derive(Serialize, Deserialize)]
+    struct DeleteTemplateForm {
+        id: String,
+    }
 
-            const YEAR: u64 = 31_536_000;
-            const MONTH: u64 = 2_592_000;
-            const DAY: u64 = 86_400;
-            const HOUR: u64 = 3600;
-            const MINUTE: u64 = 60;
-            let diff = seconds / YEAR;
-            if diff > 0 {
-                return format!("{}y ago", diff);
-            }
+    async fn delete_template(
+        db: Database,
+        user: User,
+        Json(form): Json<DeleteTemplateForm>,
+    ) -> Result<impl IntoResponse> {
+        db.delete_template(&user, &form.id).await?;
 
-            let diff = seconds / MONTH;
-            if diff > 0 {
-                return format!("{}m ago", diff);
-            }
+        Ok(res().redirect(Route::Templates))
+    }
 
-            let diff = seconds / DAY;
-            if diff > 0 {
-                return format!("{}d ago", diff);
-            }
+    #[derive(Serialize, Deserialize)]
+    struct TemplateExerciseForm {
+        template_id: String,
+        name: String,
+        #[serde(default)]
+        sets: usize,
+        #[serde(default)]
+        reps: usize,
+        #[serde(default)]
+        weight: f64,
+        #[serde(default)]
+        increment: f64,
+    }
 
-            let diff = seconds / HOUR;
-            if diff > 0 {
-                return format!("{}h ago", diff);
-            }
+    async fn create_template_exercise(
+        database: Database,
+        user: User,
+        Json(form): Json<TemplateExerciseForm>,
+    ) -> Result<impl IntoResponse> {
+        let (template, exercises) = database.template(&user, &form.template_id).await?;
+        let position = exercises
+            .last()
+            .map(|exercise| exercise.position + 1)
+            .unwrap_or_default();
+        let _ = database
+            .db
+            .insert(database.template_exercises)
+            .values(TemplateExercise::new(&template, position, form))?
+            .rows_affected()
+            .await?;
 
-            let diff = seconds / MINUTE;
-            if diff > 0 {
-                return format!("{}m ago", diff);
-            }
+        Ok(res().redirect(template_url(&template.id)))
+    }
 
-            return format!("{}s ago", seconds);
-        }
+    #[derive(Serialize, Deserialize)]
+    struct DeleteTemplateExerciseForm {
+        id: String,
+    }
 
-        fn seconds_ago(seconds: u64) -> u64 {
-            let now = now();
-            now - seconds
-        }
+    async fn delete_template_exercise(
+        db: Database,
+        user: User,
+        Json(form): Json<DeleteTemplateExerciseForm>,
+    ) -> Result<impl IntoResponse> {
+        let template_id = db.delete_template_exercise(&user, &form.id).await?;
 
-        fn set_li(set: Set) -> impl Render {
-            li.class("flex justify-between")((
-                div.class("flex flex-col gap-1 py-5")((
-                    div.class("font-bold")(set.name),
-                    div.class("flex gap-4 dark:text-gray-400 text-gray-300")((
-                        render_if(set.weight != 0, span((set.weight, " lbs"))),
-                        span((set.reps, " reps")),
-                        time_ago(set.created_at),
-                    )),
-                )),
-                form(Route::DeleteSet).class("flex justify-center items-center")((
-                    hidden_input().name("id").value(set.id),
-                    small_button()("Delete"),
-                )),
-            ))
-        }
+        Ok(res().redirect(template_url(&template_id)))
+    }
 
-        fn hidden_input() -> Tag {
-            input.r#type("hidden")
-        }
+    #[derive(Serialize, Deserialize, Clone, Copy)]
+    #[serde(rename_all = "snake_case")]
+    enum Direction {
+        Up,
+        Down,
+    }
 
-        fn small_button() -> Tag {
-            html::button
-            .class("rounded-md bg-transparent border dark:border-gray-700 border-gray-300 text-white px-3 py-1")
-        }
+    #[derive(Serialize, Deserialize)]
+    struct MoveTemplateExerciseForm {
+        id: String,
+        direction: Direction,
+    }
 
-        pub fn set_list_part(user: User, sets: Vec<Set>) -> impl Render {
-            div.class("px-4 lg:px-0 flex flex-col gap-4")((
-                h1.class("text-2xl text-center")("Sets"),
-                render_if(
-                    seconds_ago(user.created_at) < 60,
-                    div.class("bg-gray-300 dark:bg-gray-800 p-4 rounded-md flex flex-col gap-3")((
-                        div.class("flex flex-col gap-1")((
-                            p("this is your secret"),
-                            p.class("font-bold")(user.secret),
-                            p("it's the only way to log back in, don't lose it!"),
-                            span((
-                                span("you can always see your secret in "),
-                                a.class("underline").href(Route::Profile)("profile"),
-                            )),
-                        )),
-                        p("this message will self-destruct in 60s"),
+    async fn move_template_exercise(
+        db: Database,
+        user: User,
+        Json(form): Json<MoveTemplateExerciseForm>,
+    ) -> Result<impl IntoResponse> {
+        let template_id = db
+            .move_template_exercise(&user, &form.id, matches!(form.direction, Direction::Up))
+            .await?;
+
+        Ok(res().redirect(template_url(&template_id)))
+    }
+
+    async fn set_list(db: Database, user: User, Query(params): Query<SetListParams>) -> Html {
+        let deleted = match params.undo {
+            Some(ref id) => db.deleted_set(&user, id).await?,
+            None => None,
+        };
+        let sets = match params.filter.is_empty() {
+            true => db.recent_sets(&user).await?,
+            false => db.search_sets(&user, &params.filter).await?,
+        };
+        let rest = db.rest(&user).await?;
+        let response = response(
+            Route::SetList,
+            (
+                rest_timer(rest),
+                set_list_part(user, sets, deleted, params.filter),
+            ),
+        );
+
+        // the toast shows once, drop ?undo= from history so a reload or
+        // back doesn't bring it back for a delete that's been handled
+        Ok(match params.undo {
+            Some(_) => response.header(
+                HX_REPLACE_URL,
+                HeaderValue::from_str(&Route::SetList.to_string()).unwrap(),
+            ),
+            None => response,
+        })
+    }
+
+    const HX_REPLACE_URL: HeaderName = HeaderName::from_static("hx-replace-url");
+
+    #[derive(Deserialize)]
+    struct SetParams {
+        id: String,
+    }
+
+    async fn edit_set(db: Database, user: User, Query(params): Query<SetParams>) -> Html {
+        let set = db.set(&user, &params.id).await?;
+        let exercise = db.exercise(&user, &set.name).await?;
+
+        render(Route::EditSet, edit_set_part(&exercise, set, user.tz()))
+    }
+
+    #[derive(Deserialize)]
+    struct UpdateSetForm {
+        id: String,
+        #[serde(flatten)]
+        set: SetForm,
+    }
+
+    async fn update_set(
+        db: Database,
+        user: User,
+        Json(form): Json<UpdateSetForm>,
+    ) -> Result<impl IntoResponse> {
+        let mut set = db.set(&user, &form.id).await?;
+        set.edit(&user, form.set);
+        db.save_set(set).await?;
+
+        Ok(res().redirect(Route::SetList))
+    }
+
+    fn set_url(id: &str) -> String {
+        format!("{}?id={}", Route::EditSet, id)
+    }
+
+    async fn profile(db: Database, user: User) -> Result<impl IntoResponse> {
+        let bodyweight = db.bodyweights(&user).await?.latest();
+
+        Ok(response(Route::Profile, profile_part(user, bodyweight, None)).short_cache())
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct TimeZoneForm {
+        time_zone: String,
+    }
+
+    async fn update_time_zone(
+        db: Database,
+        user: User,
+        Json(form): Json<TimeZoneForm>,
+    ) -> Result<Response> {
+        match db.update_time_zone(&user, &form.time_zone).await {
+            Ok(()) => Ok(res().redirect(Route::Profile).into_response()),
+            Err(Error::Invalid(message)) => {
+                let bodyweight = db.bodyweights(&user).await?.latest();
+
+                Ok(response(
+                    Route::Profile,
+                    profile_part(user, bodyweight, Some(message)),
+                )
+                .into_response())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct PlatesParams {
+        #[serde(default, deserialize_with = "de::optional")]
+        weight: Option<f64>,
+    }
+
+    async fn plates(user: User, Query(params): Query<PlatesParams>) -> Html {
+        render(Route::Plates, plates_part(&user, params.weight, None))
+    }
+
+    /// Swapped in under the weight input as it changes, anonymous users
+    /// get the default bar and plates
+    async fn plate_hint(user: Option<User>, Query(params): Query<PlatesParams>) -> Html {
+        let user = user.unwrap_or_else(User::new);
+
+        Ok(res().render(plate_hint_part(&user, params.weight)))
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct PlatesForm {
+        bar_weight: f64,
+        plates: String,
+    }
+
+    async fn update_plates(
+        db: Database,
+        user: User,
+        Json(form): Json<PlatesForm>,
+    ) -> Result<Response> {
+        match db.update_plates(&user, form.bar_weight, &form.plates).await {
+            Ok(()) => Ok(res().redirect(Route::Plates).into_response()),
+            Err(Error::Invalid(message)) => Ok(response(
+                Route::Plates,
+                plates_part(&user, None, Some(message)),
+            )
+            .into_response()),
+            Err(err) => Err(err),
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct MeasurementsParams {
+        #[serde(default)]
+        kind: MeasurementKind,
+    }
+
+    async fn measurements(
+        db: Database,
+        user: User,
+        Query(params): Query<MeasurementsParams>,
+    ) -> Html {
+        let measurements = db.measurements(&user, params.kind).await?;
+
+        render(
+            Route::Measurements,
+            measurements_part(params.kind, measurements, user.tz()),
+        )
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct MeasurementForm {
+        kind: MeasurementKind,
+        value: f64,
+        #[serde(default)]
+        date: String,
+    }
+
+    async fn create_measurement(
+        Database {
+            db, measurements, ..
+        }: Database,
+        user: User,
+        Json(form): Json<MeasurementForm>,
+    ) -> Result<impl IntoResponse> {
+        let tz = user.tz();
+        let now = now();
+        let measured_at = match date::parse(&form.date) {
+            // backdated entries go at midday so they stay on their day, and
+            // like sets nothing lands in the future
+            Some(day) if day != date::today(tz) => {
+                (date::start_of(day, tz) + date::DAY / 2).min(now)
+            }
+            _ => now,
+        };
+        let _ = db
+            .insert(measurements)
+            .values(Measurement::new(&user, form.kind, form.value, measured_at))?
+            .rows_affected()
+            .await?;
+
+        Ok(res().redirect(format!(
+            "{}?kind={}",
+            Route::Measurements,
+            form.kind.value()
+        )))
+    }
+
+    async fn export(db: Database, user: User) -> Result<impl IntoResponse> {
+        let export = db.export(user).await?;
+        let filename = format!(
+            "attachment; filename=\"uliftbro-{}.json\"",
+            export.exported_at
+        );
+
+        Ok((
+            [
+                (
+                    CONTENT_DISPOSITION,
+                    HeaderValue::from_str(&filename).unwrap(),
+                ),
+                (CACHE_CONTROL, HeaderValue::from_static("no-store")),
+            ],
+            Json(export),
+        ))
+    }
+
+    async fn delete_account_form(_user: User) -> Html {
+        render(
+            Route::DeleteAccountForm,
+            delete_account_part(DeleteAccountForm::default()),
+        )
+    }
+
+    #[derive(Serialize, Deserialize, Default)]
+    struct DeleteAccountForm {
+        confirm: String,
+        error: Option<String>,
+    }
+
+    async fn delete_account(
+        db: Database,
+        user: User,
+        Json(form): Json<DeleteAccountForm>,
+    ) -> Result<impl IntoResponse> {
+        if form.confirm != "delete" {
+            return Ok(render(
+                Route::DeleteAccountForm,
+                delete_account_part(DeleteAccountForm {
+                    error: Some("type delete to confirm".into()),
+                    ..form
+                }),
+            )?
+            .into_response());
+        }
+        db.delete_user(&user.id).await?;
+
+        Ok(res()
+            .redirect(Route::Root)
+            .set_cookie(session_cookie(None))
+            .into_response())
+    }
+
+    async fn logout() -> impl IntoResponse {
+        res().redirect(Route::Root).set_cookie(session_cookie(None))
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct DeleteSetForm {
+        id: String,
+    }
+
+    async fn delete_set(
+        Database { db, sets, .. }: Database,
+        user: User,
+        htmx: Htmx,
+        Json(DeleteSetForm { id }): Json<DeleteSetForm>,
+    ) -> Result<impl IntoResponse> {
+        let _ = db
+            .update(sets)
+            .set(SetDeletedAt {
+                deleted_at: Some(now()),
+            })?
+            .r#where(and(eq(sets.id, &id), eq(sets.user_id, &user.id)))
+            .rows_affected()
+            .await?;
+
+        if !htmx.targeted() {
+            return Ok(res().redirect(format!("{}?undo={}", Route::SetList, id)));
+        }
+
+        let set: Set = db
+            .select()
+            .from(sets)
+            .r#where(and(eq(sets.id, id), eq(sets.user_id, user.id)))
+            .first()
+            .await?;
+
+        Ok(res().render(deleted_set_part(set)))
+    }
+
+    async fn undo_delete_set(
+        Database { db, sets, .. }: Database,
+        user: User,
+        Json(DeleteSetForm { id }): Json<DeleteSetForm>,
+    ) -> Result<impl IntoResponse> {
+        let _ = db
+            .update(sets)
+            .set(SetDeletedAt { deleted_at: None })?
+            .r#where(and(eq(sets.id, id), eq(sets.user_id, user.id)))
+            .rows_affected()
+            .await?;
+
+        Ok(res().redirect(Route::SetList))
+    }
+
+    /// Soft deleted sets can be undone for this long
+    const SET_RETENTION: u64 = 7 * 86_400;
+
+    async fn purge_deleted_sets(db: Database) {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(err) = db.purge_deleted_sets(now() - SET_RETENTION).await {
+                eprintln!("purging deleted sets failed: {}", err);
+            }
+        }
+    }
+
+    /// Empty on purpose, `hx-swap="delete"` removes whatever asked for it
+    async fn dismiss() -> impl IntoResponse {
+        res()
+    }
+
+    async fn login_form() -> Html {
+        render(
+            Route::Login,
+            login_form_part(LoginForm {
+                secret: "".into(),
+                error: None,
+            }),
+        )
+    }
+
+    #[derive(Serialize, Deserialize, Clone)]
+    struct LoginForm {
+        secret: String,
+        error: Option<String>,
+    }
+
+    async fn login(
+        Database {
+            db,
+            users,
+            sessions,
+            ..
+        }: Database,
+        Json(params): Json<LoginForm>,
+    ) -> Result<impl IntoResponse> {
+        let user: Option<User> = db
+            .select()
+            .from(users)
+            .r#where(eq(users.secret, &params.secret))
+            .first()
+            .await
+            .ok();
+        match user {
+            Some(user) => {
+                let session: Session = db
+                    .insert(sessions)
+                    .values(Session::new(&user))?
+                    .returning()
+                    .await?;
+                Ok(res()
+                    .redirect(Route::Root)
+                    .set_cookie(session_cookie(Some(session.id)))
+                    .into_response())
+            }
+            None => Ok(render(Route::LoginForm, login_form_part(params.clone())).into_response()),
+        }
+    }
+
+    mod parts {
+
+        use super::*;
+        use chrono::Datelike;
+        use dubs::{
+            html::{self, *},
+            Responder,
+        };
+
+        pub trait Render = dubs::html::Render + 'static;
+
+        pub fn root_view() -> impl Render {
+            div.class("flex flex-col gap-8")((
+                h1.class("text-2xl text-center")("u lift bro?"),
+                set_form_view(vec![], &Exercise::default(), SetForm::default(), None),
+                a.class("text-center").href(Route::LoginForm)("Already have an account?"),
+            ))
+        }
+
+        pub fn set_form_view(
+            names: Vec<String>,
+            exercise: &Exercise,
+            form: SetForm,
+            routine: Option<Routine>,
+        ) -> impl Render {
+            let exercise_input = text_input()
+                .attr("hx-get", Route::SetFields)
+                .attr("hx-trigger", "change")
+                .attr("hx-target", "#fields")
+                .attr("hx-swap", "outerHTML")
+                .attr("hx-push-url", "false");
+            let SetForm { name, set_type, .. } = form.clone();
+            let routine = routine
+                .map(|routine| routine_part(routine).render_to_string())
+                .unwrap_or_default();
+            self::form(Route::CreateSet).class("flex flex-col px-4 lg:px-0 pt-4 gap-4")((
+                raw(routine),
+                hidden_input().name("time_zone").attr1("data-time-zone"),
+                div((
+                    label("exercise"),
+                    suggest_input(exercise_input, "name", names, name, true),
+                )),
+                set_fields_part(exercise, form),
+                div((
+                    html::label.class("flex flex-col gap-1").r#for("set_type")("set type"),
+                    set_type_select(set_type),
+                )),
+                more_part(&form),
+                button()("save your set"),
+            ))
+        }
+
+        /// How the set felt and when it was done, closed unless filled in
+        fn more_part(form: &SetForm) -> impl Render {
+            let SetForm {
+                rpe,
+                rir,
+                notes,
+                performed_at,
+                ..
+            } = form.clone();
+            let mut more = details.class("flex flex-col");
+            if rpe.is_some() || rir.is_some() || notes.is_some() || performed_at.is_some() {
+                more = more.attr1("open");
+            }
+            more((
+                summary.class("dark:text-gray-400 text-gray-500 cursor-pointer")("more"),
+                div.class("flex flex-col gap-4 pt-4")((
+                    div.class("flex gap-4")((
+                        div.class("w-full")((label("rpe"), rpe_select(rpe))),
+                        div.class("w-full")((label("rir"), optional_number_input("rir", rir))),
+                    )),
+                    div((
+                        label("notes"),
+                        textarea
+                            .class(INPUT_CLASS)
+                            .name("notes")
+                            .id("notes")
+                            .attr("rows", "2")(notes.unwrap_or_default()),
+                    )),
+                    div((
+                        html::label
+                            .class("flex flex-col gap-1")
+                            .r#for("performed_at")(
+                            "done at, leave blank for now"
+                        ),
+                        input
+                            .class(INPUT_CLASS)
+                            .r#type("datetime-local")
+                            .name("performed_at")
+                            .id("performed_at")
+                            .value(performed_at.unwrap_or_default()),
+                    )),
+                )),
+            ))
+        }
+
+        pub fn edit_set_part(exercise: &Exercise, set: Set, tz: Tz) -> impl Render {
+            let id = set.id.clone();
+            let form = SetForm {
+                rpe: set.rpe,
+                rir: set.rir,
+                notes: set.notes.clone(),
+                performed_at: Some(date::format_input(set.performed_at, tz)),
+                ..SetForm::from(set)
+            };
+            div.class("flex flex-col gap-4 px-4 lg:px-0 pt-4")((
+                h1.class("text-2xl text-center")("Edit set"),
+                self::form(Route::UpdateSet).class("flex flex-col gap-4")((
+                    hidden_input().name("id").value(id),
+                    div((
+                        label("exercise"),
+                        text_input()
+                            .name("name")
+                            .id("name")
+                            .value(form.name.clone()),
+                    )),
+                    set_fields_part(exercise, form.clone()),
+                    div((
+                        html::label.class("flex flex-col gap-1").r#for("set_type")("set type"),
+                        set_type_select(form.set_type),
                     )),
+                    more_part(&form),
+                    button()("save changes"),
+                )),
+                a.class("text-center underline").href(Route::SetList)("cancel"),
+            ))
+        }
+
+        /// Where the lifter is in a template, the set form itself is
+        /// pre-filled from the current exercise
+        fn routine_part(routine: Routine) -> impl Render {
+            let Routine {
+                template,
+                exercises,
+                step,
+                done,
+            } = routine;
+            let total = exercises.len();
+            let target = match exercises.get(step) {
+                Some(current) => format!(
+                    "{} of {} sets done, target {} × {} × {} lbs",
+                    done, current.sets, current.sets, current.reps, current.weight
                 ),
+                None => "this template has no exercises yet".to_owned(),
+            };
+            let previous = render_if(
+                step > 0,
+                a.class("underline")
+                    .href(routine_url(&template.id, step.saturating_sub(1)))(
+                    "previous"
+                ),
+            );
+            let next = match step + 1 < total {
+                true => a
+                    .class("underline")
+                    .href(routine_url(&template.id, step + 1))(
+                    "next exercise"
+                )
+                .render_to_string(),
+                false => a.class("underline").href(Route::SetList)("finish").render_to_string(),
+            };
+            div.class("flex flex-col gap-2 rounded-md bg-gray-300 dark:bg-gray-800 p-4")((
+                hidden_input().name("template").value(template.id.clone()),
+                hidden_input()
+                    .attr1("data-number")
+                    .name("step")
+                    .value(step.to_string()),
+                div.class("flex justify-between")((
+                    span.class("font-bold")(template.name),
+                    span(format!("{} of {}", (step + 1).min(total), total)),
+                )),
+                p.class("dark:text-gray-400 text-gray-500")(target),
+                div.class("flex justify-between")((previous, raw(next))),
+            ))
+        }
+
+        pub fn templates_part(templates: Vec<Template>) -> impl Render {
+            div.class("flex flex-col gap-8 px-4 lg:px-0")((
+                h1.class("text-2xl text-center")("Templates"),
                 ul.class("divide-y divide-gray-100 dark:divide-gray-800")(
-                    sets.into_iter().map(set_li).collect::<Vec<_>>(),
+                    templates
+                        .into_iter()
+                        .map(|template| {
+                            li.class("flex justify-between items-center py-5")((
+                                div.class("font-bold")(template.name),
+                                div.class("flex gap-4")((
+                                    a.class("underline").href(template_url(&template.id))("edit"),
+                                    a.class("underline text-orange-500")
+                                        .href(start_url(&template))(
+                                        "start"
+                                    ),
+                                )),
+                            ))
+                        })
+                        .collect::<Vec<_>>(),
                 ),
-                div.class("invisible lg:visible")(link_button().href(Route::Root)(
-                    "start another set",
+                form(Route::CreateTemplate).class("flex gap-4 items-end")((
+                    div.class("w-full")((
+                        html::label.class("flex flex-col gap-1").r#for("name")("new template"),
+                        text_input()
+                            .name("name")
+                            .id("name")
+                            .attr("placeholder", "push day")
+                            .attr1("required"),
+                    )),
+                    small_button()("Create"),
                 )),
             ))
         }
 
-        pub fn link_button() -> Tag {
-            a.class("flex rounded-md bg-orange-500 active:bg-orange-700 text-white p-4 items-center justify-center uppercase w-full")
-        }
+        pub fn edit_template_part(
+            template: Template,
+            exercises: Vec<TemplateExercise>,
+            names: Vec<String>,
+        ) -> impl Render {
+            let count = exercises.len();
+            div.class("flex flex-col gap-8 px-4 lg:px-0")((
+                form(Route::UpdateTemplate).class("flex gap-4 items-center")((
+                    hidden_input().name("id").value(template.id.clone()),
+                    text_input().name("name").value(template.name.clone()),
+                    program_select(template.program),
+                    small_button()("Save"),
+                )),
+                ol.class("divide-y divide-gray-100 dark:divide-gray-800")(
+                    exercises
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, exercise)| {
+                            template_exercise_li(exercise, index > 0, index + 1 < count)
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+                form(Route::CreateTemplateExercise).class("flex flex-col gap-4")((
+                    hidden_input()
+                        .name("template_id")
+                        .value(template.id.clone()),
+                    div((
+                        label("exercise"),
+                        suggest_input(text_input(), "name", names, String::new(), false),
+                    )),
+                    div.class("flex gap-4")((
+                        div.class("w-full")((label("sets"), number_input("sets", 3))),
+                        div.class("w-full")((label("reps"), number_input("reps", 5))),
+                        div.class("w-full")((label("weight"), weight_input("weight", 0.0))),
+                        div.class("w-full")((label("increment"), weight_input("increment", 5.0))),
+                    )),
+                    p.class("text-sm dark:text-gray-400 text-gray-500")(
+                        "linear progression adds the increment after every successful session, 5/3/1 takes the weight as your training max and adds it each cycle",
+                    ),
+                    button()("add exercise"),
+                )),
+                div.class("flex justify-between items-center")((
+                    a.class("underline text-orange-500")
+                        .href(start_url(&template))("start"),
+                    form(Route::DeleteTemplate)
+                        .attr("hx-confirm", format!("Delete {}?", template.name))(
+                        (
+                        hidden_input().name("id").value(template.id),
+                        small_button()("Delete template"),
+                    )
+                    ),
+                )),
+            ))
+        }
+
+        fn template_exercise_li(exercise: TemplateExercise, up: bool, down: bool) -> impl Render {
+            let move_button = |direction: &'static str, label: &'static str| {
+                form(Route::MoveTemplateExercise)((
+                    hidden_input().name("id").value(exercise.id.clone()),
+                    hidden_input().name("direction").value(direction),
+                    small_button()(label),
+                ))
+            };
+            li.class("flex justify-between items-center py-5")((
+                div.class("flex flex-col gap-1")((
+                    div.class("font-bold")(exercise.name.clone()),
+                    div.class("dark:text-gray-400 text-gray-500")(format!(
+                        "{} × {} × {} lbs, +{}",
+                        exercise.sets, exercise.reps, exercise.weight, exercise.increment
+                    )),
+                )),
+                div.class("flex gap-2")((
+                    render_if(up, move_button("up", "↑")),
+                    render_if(down, move_button("down", "↓")),
+                    form(Route::DeleteTemplateExercise)((
+                        hidden_input().name("id").value(exercise.id.clone()),
+                        small_button()("Remove"),
+                    )),
+                )),
+            ))
+        }
+
+        fn program_select(value: Program) -> impl Render {
+            select.class(INPUT_CLASS).name("program")(
+                Program::ALL
+                    .into_iter()
+                    .map(|program| {
+                        let tag = option.value(program.value());
+                        let tag = match program == value {
+                            true => tag.attr1("selected"),
+                            false => tag,
+                        };
+                        tag(program.label())
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        /// Today's prescription, sets already logged today are ticked off
+        /// in order and the rest are one tap from being logged
+        pub fn program_part(
+            template: Template,
+            checklist: Vec<(program::Prescription, usize)>,
+        ) -> impl Render {
+            div.class("flex flex-col gap-8 px-4 lg:px-0")((
+                h1.class("text-2xl text-center")(template.name),
+                checklist
+                    .into_iter()
+                    .map(|(prescription, done)| {
+                        let program::Prescription {
+                            exercise,
+                            targets,
+                            note,
+                        } = prescription;
+                        div.class("flex flex-col gap-2")((
+                            div.class("flex justify-between items-baseline")((
+                                h2.class("font-bold")(exercise.name.clone()),
+                                span.class("text-sm dark:text-gray-400 text-gray-500")(note),
+                            )),
+                            ul.class("divide-y divide-gray-100 dark:divide-gray-800")(
+                                targets
+                                    .into_iter()
+                                    .enumerate()
+                                    .map(|(index, target)| {
+                                        checklist_li(
+                                            &template.id,
+                                            &exercise.name,
+                                            target,
+                                            index < done,
+                                        )
+                                    })
+                                    .collect::<Vec<_>>(),
+                            ),
+                        ))
+                    })
+                    .collect::<Vec<_>>(),
+            ))
+        }
+
+        fn checklist_li(
+            template_id: &str,
+            name: &str,
+            target: program::Target,
+            done: bool,
+        ) -> impl Render {
+            let reps = match target.set_type {
+                SetType::Amrap => format!("{}+", target.reps),
+                _ => target.reps.to_string(),
+            };
+            if done {
+                return raw(li.class("flex justify-between py-3 opacity-60")((
+                    span.class("line-through")(format!("{} × {} lbs", reps, target.weight)),
+                    span("✓"),
+                ))
+                .render_to_string());
+            }
+            raw(
+                li.class("py-3")(form(Route::CheckSet).class("flex gap-4 items-center")((
+                    hidden_input()
+                        .name("template_id")
+                        .value(template_id.to_owned()),
+                    hidden_input().name("name").value(name.to_owned()),
+                    hidden_input()
+                        .name("set_type")
+                        .value(target.set_type.value()),
+                    number_input("reps", target.reps),
+                    span("×"),
+                    weight_input("weight", target.weight),
+                    small_button()(format!("Done {}", reps)),
+                )))
+                .render_to_string(),
+            )
+        }
+
+        /// The reps, weight, time and distance inputs, whichever the exercise
+        /// is measured in
+        pub fn set_fields_part(exercise: &Exercise, form: SetForm) -> impl Render {
+            let metrics = exercise.metrics;
+            // assisted pull-ups and the like take away weight, so no min here
+            let weight_label = match exercise.load {
+                Load::External => "weight",
+                Load::Bodyweight => "added weight",
+            };
+            div.id("fields").class("flex gap-4")((
+                render_if(
+                    metrics.reps(),
+                    div.class("w-full")((label("reps"), number_input("reps", form.reps))),
+                ),
+                render_if(
+                    metrics.weight(),
+                    div.class("w-full")((
+                        html::label.class("flex flex-col gap-1").r#for("weight")(weight_label),
+                        weight_input("weight", form.weight),
+                        render_if(exercise.load == Load::External, plate_hint_loader()),
+                    )),
+                ),
+                render_if(
+                    metrics.duration(),
+                    div.class("w-full")((
+                        html::label.class("flex flex-col gap-1").r#for("duration")("seconds"),
+                        optional_number_input("duration", form.duration),
+                    )),
+                ),
+                render_if(
+                    metrics.distance(),
+                    div.class("w-full")((
+                        html::label.class("flex flex-col gap-1").r#for("distance")("meters"),
+                        optional_number_input("distance", form.distance),
+                    )),
+                ),
+            ))
+        }
+
+        /// Fetches the hint for whatever's in the weight input, so it
+        /// follows typing without the set form knowing the user's plates
+        fn plate_hint_loader() -> impl Render {
+            div.class("pt-1 text-sm dark:text-gray-400 text-gray-500")
+                .attr("hx-get", Route::PlateHint)
+                .attr("hx-trigger", "load, input delay:300ms from:#fields")
+                .attr("hx-include", "#fields [name='weight']")
+                .attr("hx-swap", "innerHTML")
+                .attr("hx-push-url", "false")("")
+        }
+
+        pub fn plate_hint_part(user: &User, weight: Option<f64>) -> impl Render {
+            let hint = weight
+                .filter(|weight| *weight > 0.0)
+                .and_then(|weight| plates::load(weight, user.bar_weight, &user.plates()))
+                .filter(|loading| !loading.per_side.is_empty())
+                .map(|loading| {
+                    let per_side = loading
+                        .per_side
+                        .iter()
+                        .map(|plate| plate.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" + ");
+                    match loading.missing > 0.0 {
+                        true => format!("{} a side, {} short", per_side, loading.missing),
+                        false => format!("{} a side", per_side),
+                    }
+                })
+                .unwrap_or_default();
+
+            span(hint)
+        }
+
+        pub fn plates_part(user: &User, weight: Option<f64>, error: Option<String>) -> impl Render {
+            let result = match weight {
+                Some(weight) => plates_result(user, weight).render_to_string(),
+                None => String::new(),
+            };
+            div.class("flex flex-col gap-8 px-4 lg:px-0")((
+                h1.class("text-2xl text-center")("Plates"),
+                html::form
+                    .method("get")
+                    .action(Route::Plates)
+                    .class("flex flex-col gap-4")
+                    .attr("hx-get", Route::Plates)
+                    .attr("hx-trigger", "input changed delay:300ms")
+                    .attr("hx-target", "#plates-result")
+                    .attr("hx-select", "#plates-result")
+                    .attr("hx-swap", "outerHTML")
+                    .attr("hx-push-url", "true")(div((
+                    label("weight"),
+                    input
+                        .class(INPUT_CLASS)
+                        .r#type("number")
+                        .attr("step", "any")
+                        .attr("min", "0")
+                        .name("weight")
+                        .id("weight")
+                        .value(weight.map(|weight| weight.to_string()).unwrap_or_default()),
+                ))),
+                div.id("plates-result")(raw(result)),
+                form(Route::UpdatePlates).class("flex flex-col gap-4")((
+                    div.class("flex gap-4")((
+                        div.class("w-1/3")((
+                            html::label.class("flex flex-col gap-1").r#for("bar_weight")("bar"),
+                            input
+                                .class(INPUT_CLASS)
+                                .r#type("number")
+                                .attr("step", "any")
+                                .attr("min", "0")
+                                .attr1("required")
+                                .attr1("data-number")
+                                .name("bar_weight")
+                                .id("bar_weight")
+                                .value(user.bar_weight.to_string()),
+                        )),
+                        div.class("w-full")((
+                            label("plates"),
+                            text_input()
+                                .attr1("required")
+                                .name("plates")
+                                .id("plates")
+                                .value(plates::format(&user.plates())),
+                        )),
+                    )),
+                    p.class("text-sm dark:text-gray-400 text-gray-500")(
+                        "the plates you have, 45x4 if you only have four of them",
+                    ),
+                    p.class("text-sm text-red-500")(error.unwrap_or_default()),
+                    small_button()("Save"),
+                )),
+            ))
+        }
+
+        /// Each side drawn as a stack of plates, widest first
+        fn plates_result(user: &User, weight: f64) -> impl Render {
+            let plates = user.plates();
+            let Some(loading) = plates::load(weight, user.bar_weight, &plates) else {
+                return raw(
+                    p(format!("that's lighter than the {} bar", user.bar_weight))
+                        .render_to_string(),
+                );
+            };
+            let heaviest = plates.first().map(|plate| plate.weight).unwrap_or(1.0);
+            let stack = loading
+                .per_side
+                .iter()
+                .map(|plate| {
+                    // taller for heavier plates, in steps since the csp
+                    // blocks inline styles
+                    let class = match plate / heaviest {
+                        ratio if ratio > 0.75 => "flex items-center justify-center w-8 h-24 rounded-sm bg-orange-500 text-white text-xs",
+                        ratio if ratio > 0.5 => "flex items-center justify-center w-8 h-20 rounded-sm bg-orange-500 text-white text-xs",
+                        ratio if ratio > 0.25 => "flex items-center justify-center w-8 h-16 rounded-sm bg-orange-500 text-white text-xs",
+                        ratio if ratio > 0.1 => "flex items-center justify-center w-8 h-12 rounded-sm bg-orange-500 text-white text-xs",
+                        _ => "flex items-center justify-center w-8 h-8 rounded-sm bg-orange-500 text-white text-xs",
+                    };
+                    div.class(class)(plate.to_string())
+                })
+                .collect::<Vec<_>>();
+            let summary = match loading.per_side.is_empty() {
+                true => "just the bar".to_owned(),
+                false => format!(
+                    "{} a side",
+                    loading
+                        .per_side
+                        .iter()
+                        .map(|plate| plate.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" + ")
+                ),
+            };
+            raw(div.class("flex flex-col gap-4")((
+                p.class("font-bold")(summary),
+                div.class("flex items-center gap-1 h-24")((
+                    div.class("h-2 w-16 rounded-sm bg-gray-400"),
+                    stack,
+                )),
+                render_if(
+                    loading.missing > 0.0,
+                    p.class("text-sm text-red-500")(format!(
+                        "{} short, your plates can't make it exactly",
+                        loading.missing
+                    )),
+                ),
+            ))
+            .render_to_string())
+        }
+
+        fn format_duration(seconds: usize) -> String {
+            match seconds >= 3600 {
+                true => format!(
+                    "{}:{:02}:{:02}",
+                    seconds / 3600,
+                    seconds % 3600 / 60,
+                    seconds % 60
+                ),
+                false => format!("{}:{:02}", seconds / 60, seconds % 60),
+            }
+        }
+
+        /// Time since the last set, rest-timer.js keeps it ticking and
+        /// cues when the exercise's target rest is up
+        pub fn rest_timer(rest: Option<Rest>) -> impl Render {
+            let Some(rest) = rest else {
+                return raw(String::new());
+            };
+            let elapsed = now().saturating_sub(rest.since) as usize;
+            let target = rest
+                .target
+                .map(|target| format!(" / {}", format_duration(target)))
+                .unwrap_or_default();
+            raw(div
+                .class("flex justify-between items-center px-4 lg:px-0 pt-4 dark:text-gray-400 text-gray-500")
+                .attr("data-rest-since", rest.since.to_string())
+                .attr("data-rest-target", rest.target.unwrap_or_default().to_string())((
+                span((
+                    "rest ",
+                    span.class("font-bold").attr1("data-rest-elapsed")(format_duration(elapsed)),
+                    target,
+                    " since ",
+                    rest.name,
+                )),
+                html::button
+                    .r#type("button")
+                    .class("underline")
+                    .attr1("data-rest-cue")("cue off"),
+            ))
+            .render_to_string())
+        }
+
+        pub fn exercises_part(exercises: Vec<Exercise>) -> impl Render {
+            div.class("px-4 lg:px-0 flex flex-col gap-4")((
+                h1.class("text-2xl text-center")("Exercises"),
+                p.class("dark:text-gray-400 text-gray-500")(
+                    "pick what you track for each exercise and how long to rest between sets, the set form only shows those inputs",
+                ),
+                ul.class("divide-y divide-gray-100 dark:divide-gray-800")(
+                    exercises.into_iter().map(exercise_li).collect::<Vec<_>>(),
+                ),
+            ))
+        }
+
+        fn exercise_li(exercise: Exercise) -> impl Render {
+            li.class("flex flex-col gap-2 py-5")((
+                div.class("font-bold")(exercise.name.clone()),
+                form(Route::UpdateExercise).class("flex gap-4 items-center")((
+                    hidden_input().name("name").value(exercise.name),
+                    metrics_select(exercise.metrics),
+                    load_select(exercise.load),
+                    muscle_group_select(exercise.muscle_group),
+                    input
+                        .class(INPUT_CLASS)
+                        .r#type("number")
+                        .attr("min", "0")
+                        .attr("placeholder", "rest (s)")
+                        .attr1("data-number")
+                        .name("rest")
+                        .value(
+                            exercise
+                                .rest
+                                .map(|rest| rest.to_string())
+                                .unwrap_or_default(),
+                        ),
+                    small_button()("Save"),
+                )),
+            ))
+        }
+
+        fn metrics_select(value: Metrics) -> impl Render {
+            select.class(INPUT_CLASS).name("metrics")(
+                Metrics::ALL
+                    .into_iter()
+                    .map(|metrics| {
+                        let tag = option.value(metrics.value());
+                        let tag = match metrics == value {
+                            true => tag.attr1("selected"),
+                            false => tag,
+                        };
+                        tag(metrics.label())
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        fn muscle_group_select(value: Option<MuscleGroup>) -> impl Render {
+            let options = MuscleGroup::ALL
+                .into_iter()
+                .map(|muscle_group| {
+                    let tag = option.value(muscle_group.value());
+                    let tag = match Some(muscle_group) == value {
+                        true => tag.attr1("selected"),
+                        false => tag,
+                    };
+                    tag(muscle_group.label())
+                })
+                .collect::<Vec<_>>();
+
+            select.class(INPUT_CLASS).name("muscle_group")((option.value("")("-"), options))
+        }
+
+        pub fn stats_part(stats: TrainingStats) -> impl Render {
+            let TrainingStats {
+                weeks,
+                muscle_groups,
+                exercises,
+                streak,
+                longest_streak,
+            } = stats;
+            let this_week = weeks.last().cloned().unwrap_or_default();
+            let label = |week: &Week| date::naive(week.monday).format("%-m/%-d").to_string();
+            let tonnage = weeks
+                .iter()
+                .map(|week| (label(week), week.tonnage))
+                .collect::<Vec<_>>();
+            let days = weeks
+                .iter()
+                .map(|week| (label(week), week.days))
+                .collect::<Vec<_>>();
+            let most_sets = exercises
+                .iter()
+                .map(|row| row.sets)
+                .max()
+                .unwrap_or_default();
+            let most_muscle_group_sets = muscle_groups
+                .iter()
+                .map(|row| row.sets)
+                .max()
+                .unwrap_or_default();
+
+            div.class("flex flex-col gap-8 px-4 lg:px-0")((
+                h1.class("text-2xl text-center")("Stats"),
+                div.class("grid grid-cols-2 gap-4")((
+                    stat("this week", format!("{} lbs", this_week.tonnage)),
+                    stat("days this week", this_week.days.to_string()),
+                    stat("week streak", streak.to_string()),
+                    stat("longest streak", longest_streak.to_string()),
+                )),
+                section.class("flex flex-col gap-2")((
+                    h2.class("font-bold")("weekly tonnage"),
+                    bar_chart(tonnage),
+                )),
+                section.class("flex flex-col gap-2")((
+                    h2.class("font-bold")("training days per week"),
+                    bar_chart(days),
+                )),
+                section.class("flex flex-col gap-2")((
+                    h2.class("font-bold")(format!(
+                        "sets per muscle group, last {} weeks",
+                        STATS_WEEKS
+                    )),
+                    table.class("w-full")(tbody(
+                        muscle_groups
+                            .into_iter()
+                            .map(|row| {
+                                let label = row
+                                    .muscle_group
+                                    .map(|muscle_group| muscle_group.label())
+                                    .unwrap_or("unassigned");
+                                count_row(label.to_owned(), row.sets, most_muscle_group_sets)
+                            })
+                            .collect::<Vec<_>>(),
+                    )),
+                    a.class("underline text-sm dark:text-gray-400 text-gray-500")
+                        .href(Route::Exercises)("assign muscle groups"),
+                )),
+                section.class("flex flex-col gap-2")((
+                    h2.class("font-bold")(format!("sets per exercise, last {} weeks", STATS_WEEKS)),
+                    table.class("w-full")(tbody(
+                        exercises
+                            .into_iter()
+                            .map(|row| count_row(row.name, row.sets, most_sets))
+                            .collect::<Vec<_>>(),
+                    )),
+                )),
+                section.class("flex flex-col gap-2")((
+                    h2.class("font-bold")("by week"),
+                    table.class("w-full text-left")((
+                        thead(tr((th("week"), th("days"), th("sets"), th("tonnage")))),
+                        tbody(
+                            weeks
+                                .iter()
+                                .rev()
+                                .map(|week| {
+                                    tr((
+                                        td(date::format(week.monday)),
+                                        td(week.days.to_string()),
+                                        td(week.sets.to_string()),
+                                        td(week.tonnage.to_string()),
+                                    ))
+                                })
+                                .collect::<Vec<_>>(),
+                        ),
+                    )),
+                )),
+            ))
+        }
+
+        fn stat(label: &'static str, value: String) -> impl Render {
+            div.class("flex flex-col gap-1 rounded-md bg-gray-300 dark:bg-gray-800 p-4")((
+                span.class("text-sm dark:text-gray-400 text-gray-500")(label),
+                span.class("text-2xl font-bold")(value),
+            ))
+        }
+
+        fn count_row(label: String, sets: i64, most: i64) -> impl Render {
+            tr((
+                td.class("py-1 pr-4 whitespace-nowrap")(label),
+                td.class("w-full")(meter(sets, most)),
+                td.class("py-1 pl-4 text-right")(sets.to_string()),
+            ))
+        }
+
+        /// A horizontal bar as wide as `value` is of `max`
+        fn meter(value: i64, max: i64) -> impl Render {
+            let width = value as f64 / max.max(1) as f64 * 100.0;
+            raw(format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 8" preserveAspectRatio="none" class="w-full h-2"><rect width="{width:.1}" height="8" rx="1" fill="rgb(249 115 22)"/></svg>"#
+            ))
+        }
+
+        /// Vertical bars scaled to the tallest one with a label under each
+        fn bar_chart(bars: Vec<(String, i64)>) -> impl Render {
+            const WIDTH: f64 = 400.0;
+            const HEIGHT: f64 = 160.0;
+            const LABEL: f64 = 14.0;
+
+            let max = bars
+                .iter()
+                .map(|(_, value)| *value)
+                .max()
+                .unwrap_or_default()
+                .max(1) as f64;
+            let slot = WIDTH / bars.len().max(1) as f64;
+            let bars = bars
+                .iter()
+                .enumerate()
+                .map(|(index, (label, value))| {
+                    let height = *value as f64 / max * (HEIGHT - LABEL * 2.0);
+                    let x = index as f64 * slot;
+                    format!(
+                        r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" rx="2" fill="rgb(249 115 22)"><title>{}: {}</title></rect><text x="{:.1}" y="{}" font-size="9" text-anchor="middle" fill="currentColor">{}</text>"#,
+                        x + slot * 0.15,
+                        HEIGHT - LABEL - height,
+                        slot * 0.7,
+                        height,
+                        escape(label),
+                        value,
+                        x + slot / 2.0,
+                        HEIGHT - 2.0,
+                        escape(label),
+                    )
+                })
+                .collect::<String>();
+
+            raw(format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {HEIGHT}" class="w-full h-40">{bars}</svg>"#
+            ))
+        }
+
+        /// Weeks on the heatmap, a year plus the one in progress
+        const HEATMAP_WEEKS: i64 = 53;
+
+        /// Monday of the first week on the heatmap, rows are monday to sunday
+        pub fn heatmap_start(today: i64) -> i64 {
+            date::monday(today) - (HEATMAP_WEEKS - 1) * 7
+        }
+
+        /// A year of training days as a grid of squares, darker for more
+        /// sets, each one linking to the sets logged that day
+        pub fn heatmap(days: Vec<TrainingDay>, today: i64) -> impl Render {
+            const CELL: i64 = 12;
+            const TOP: i64 = 14;
+            const MONTHS: [&str; 12] = [
+                "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+            ];
+
+            let start = heatmap_start(today);
+            let mut cells = String::new();
+            for week in 0..HEATMAP_WEEKS {
+                let monday = start + week * 7;
+                let naive = date::naive(monday);
+                if naive.day() <= 7 {
+                    cells.push_str(&format!(
+                        r#"<text x="{}" y="10" font-size="9" fill="currentColor">{}</text>"#,
+                        week * CELL,
+                        MONTHS[naive.month0() as usize]
+                    ));
+                }
+                for weekday in 0..7 {
+                    let day = monday + weekday;
+                    if day > today {
+                        break;
+                    }
+                    let sets = days
+                        .iter()
+                        .find(|training_day| training_day.day == day)
+                        .map(|training_day| training_day.sets)
+                        .unwrap_or_default();
+                    let ymd = date::format(day);
+                    let (fill, opacity) = match sets {
+                        0 => ("rgb(156 163 175)", 0.2),
+                        1..=5 => ("rgb(249 115 22)", 0.4),
+                        6..=10 => ("rgb(249 115 22)", 0.6),
+                        11..=20 => ("rgb(249 115 22)", 0.8),
+                        _ => ("rgb(249 115 22)", 1.0),
+                    };
+                    let cell = format!(
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" rx="2" fill="{}" fill-opacity="{}"><title>{}: {} sets</title></rect>"#,
+                        week * CELL,
+                        TOP + weekday * CELL,
+                        CELL - 2,
+                        CELL - 2,
+                        fill,
+                        opacity,
+                        ymd,
+                        sets
+                    );
+                    match sets {
+                        0 => cells.push_str(&cell),
+                        _ => cells.push_str(&format!(
+                            r#"<a href="{}?from={}&amp;to={}">{}</a>"#,
+                            Route::SetList,
+                            ymd,
+                            ymd,
+                            cell
+                        )),
+                    }
+                }
+            }
+            let width = HEATMAP_WEEKS * CELL;
+            let height = TOP + 7 * CELL;
+
+            raw(format!(
+                r#"<section class="flex flex-col gap-2 px-4 lg:px-0 pt-8"><h2 class="font-bold">training days</h2><svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" class="w-full">{cells}</svg></section>"#
+            ))
+        }
+
+        /// For text going into hand written svg
+        fn escape(s: &str) -> String {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+                .replace('\'', "&#39;")
+        }
+
+        fn load_select(value: Load) -> impl Render {
+            select.class(INPUT_CLASS).name("load")(
+                Load::ALL
+                    .into_iter()
+                    .map(|load| {
+                        let tag = option.value(load.value());
+                        let tag = match load == value {
+                            true => tag.attr1("selected"),
+                            false => tag,
+                        };
+                        tag(load.label())
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        fn set_type_select(value: SetType) -> impl Render {
+            select.class(INPUT_CLASS).name("set_type").id("set_type")(
+                SetType::ALL
+                    .into_iter()
+                    .map(|set_type| {
+                        let tag = option.value(set_type.value());
+                        let tag = match set_type == value {
+                            true => tag.attr1("selected"),
+                            false => tag,
+                        };
+                        tag(set_type.label())
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        fn set_type_badge(set_type: SetType) -> impl Render {
+            render_if(
+                set_type != SetType::Working,
+                span.class("text-xs uppercase rounded-md px-2 py-0.5 border dark:border-gray-700 border-gray-300 dark:text-gray-400 text-gray-500")(
+                    set_type.label(),
+                ),
+            )
+        }
+
+        /// 6 to 10 in halves, anything lower isn't worth rating
+        fn rpe_select(value: Option<f64>) -> impl Render {
+            let options = (12..=20)
+                .map(|halves| halves as f64 / 2.0)
+                .map(|rpe| {
+                    let tag = option.value(rpe.to_string());
+                    let tag = match value == Some(rpe) {
+                        true => tag.attr1("selected"),
+                        false => tag,
+                    };
+                    tag(rpe.to_string())
+                })
+                .collect::<Vec<_>>();
+
+            select
+                .class(INPUT_CLASS)
+                .attr1("data-number")
+                .name("rpe")
+                .id("rpe")((option.value("")("-"), options))
+        }
+
+        fn render_if(is_true: bool, part: impl Render) -> impl Render {
+            raw(if is_true {
+                part.render_to_string()
+            } else {
+                String::with_capacity(0)
+            })
+        }
+
+        fn time_ago(seconds: u64) -> impl Render {
+            let seconds = now().saturating_sub(seconds);
+
+            const YEAR: u64 = 31_536_000;
+            const MONTH: u64 = 2_592_000;
+            const DAY: u64 = 86_400;
+            const HOUR: u64 = 3600;
+            const MINUTE: u64 = 60;
+            let diff = seconds / YEAR;
+            if diff > 0 {
+                return format!("{}y ago", diff);
+            }
+
+            let diff = seconds / MONTH;
+            if diff > 0 {
+                return format!("{}mo ago", diff);
+            }
+
+            let diff = seconds / DAY;
+            if diff > 0 {
+                return format!("{}d ago", diff);
+            }
+
+            let diff = seconds / HOUR;
+            if diff > 0 {
+                return format!("{}h ago", diff);
+            }
+
+            let diff = seconds / MINUTE;
+            if diff > 0 {
+                return format!("{}min ago", diff);
+            }
+
+            return format!("{}s ago", seconds);
+        }
+
+        fn seconds_ago(seconds: u64) -> u64 {
+            let now = now();
+            now - seconds
+        }
+
+        /// "bw + 10 lbs" for bodyweight exercises, plain lbs for the rest
+        fn weight_span(set: &LoggedSet) -> impl Render {
+            let weight = set.set.weight;
+            match set.load {
+                Load::External => render_if(weight != 0.0, span(format!("{} lbs", weight))),
+                Load::Bodyweight => render_if(
+                    true,
+                    span(match weight {
+                        w if w > 0.0 => format!("bw + {} lbs", w),
+                        w if w < 0.0 => format!("bw - {} lbs", -w),
+                        _ => "bw".to_owned(),
+                    }),
+                ),
+            }
+        }
+
+        fn e1rm_span(set: &LoggedSet) -> impl Render {
+            let e1rm = set.e1rm();
+            render_if(
+                e1rm.is_some(),
+                span(format!("e1rm {:.0}", e1rm.unwrap_or_default())),
+            )
+        }
+
+        fn set_li(logged: LoggedSet, tz: Tz) -> impl Render {
+            let weight = weight_span(&logged);
+            let e1rm = e1rm_span(&logged);
+            let set = logged.set;
+            // warm-ups are dimmed since they don't count toward volume
+            let class = match set.set_type.counts() {
+                true => "flex justify-between",
+                false => "flex justify-between opacity-60",
+            };
+            li.id(format!("set-{}", set.id)).class(class)((
+                div.class("flex flex-col gap-1 py-5")((
+                    div.class("flex gap-2 items-center")((
+                        a.class("font-bold").href(set_url(&set.id))(set.name.clone()),
+                        set_type_badge(set.set_type),
+                    )),
+                    div.class("flex gap-4 dark:text-gray-400 text-gray-300")((
+                        weight,
+                        render_if(set.reps != 0, span((set.reps, " reps"))),
+                        e1rm,
+                        render_if(
+                            set.duration.is_some(),
+                            span(format_duration(set.duration.unwrap_or_default())),
+                        ),
+                        render_if(
+                            set.distance.is_some(),
+                            span(format!("{} m", set.distance.unwrap_or_default())),
+                        ),
+                        render_if(
+                            set.rpe.is_some(),
+                            span(format!("rpe {}", set.rpe.unwrap_or_default())),
+                        ),
+                        render_if(
+                            set.rir.is_some(),
+                            span(format!("{} rir", set.rir.unwrap_or_default())),
+                        ),
+                        span.attr("title", date::format_time(set.performed_at, tz))(time_ago(
+                            set.performed_at,
+                        )),
+                    )),
+                    render_if(
+                        set.notes.is_some(),
+                        p.class("text-sm dark:text-gray-400 text-gray-500")(
+                            set.notes.clone().unwrap_or_default(),
+                        ),
+                    ),
+                )),
+                form(Route::DeleteSet)
+                    .class("flex justify-center items-center")
+                    .attr("hx-post", Route::DeleteSet)
+                    .attr("hx-target", "closest li")
+                    .attr("hx-swap", "outerHTML")
+                    .attr("hx-push-url", "false")
+                    .attr("hx-confirm", format!("Delete this {} set?", set.name))((
+                    hidden_input().name("id").value(set.id),
+                    small_button()("Delete"),
+                )),
+            ))
+        }
+
+        fn hidden_input() -> Tag {
+            input.r#type("hidden")
+        }
+
+        fn small_button() -> Tag {
+            html::button
+            .class("rounded-md bg-transparent border dark:border-gray-700 border-gray-300 text-white px-3 py-1")
+        }
+
+        pub fn undo_toast(set: Set) -> impl Render {
+            div.class("fixed bottom-20 lg:bottom-8 inset-x-4 lg:inset-x-0 lg:mx-auto lg:max-w-md bg-gray-300 dark:bg-gray-800 p-4 rounded-md flex justify-between items-center")
+                .attr("hx-get", Route::Dismiss)
+                .attr("hx-trigger", "load delay:5s")
+                .attr("hx-swap", "delete")
+                .attr("hx-push-url", "false")((
+                span(("deleted ", set.name)),
+                form(Route::UndoDeleteSet)((
+                    hidden_input().name("id").value(set.id),
+                    small_button()("Undo"),
+                )),
+            ))
+        }
+
+        pub fn set_list_part(
+            user: User,
+            sets: Vec<LoggedSet>,
+            deleted: Option<Set>,
+            filter: SetFilter,
+        ) -> impl Render {
+            let filtered = !filter.is_empty();
+            let count = sets.len();
+            let tz = user.tz();
+            let mut current = None;
+            let mut items = vec![];
+            for logged in sets {
+                let day = date::day(logged.set.performed_at, tz);
+                if current != Some(day) {
+                    current = Some(day);
+                    items.push(
+                        li.class("pt-6 pb-1 text-sm dark:text-gray-400 text-gray-500")(
+                            date::format(day),
+                        )
+                        .render_to_string(),
+                    );
+                }
+                items.push(set_li(logged, tz).render_to_string());
+            }
+            div.class("px-4 lg:px-0 flex flex-col gap-4")((
+                h1.class("text-2xl text-center")("Sets"),
+                set_filter_part(filter),
+                render_if(
+                    seconds_ago(user.created_at) < 60,
+                    div.class("bg-gray-300 dark:bg-gray-800 p-4 rounded-md flex flex-col gap-3")((
+                        div.class("flex flex-col gap-1")((
+                            p("this is your secret"),
+                            p.class("font-bold")(user.secret),
+                            p("it's the only way to log back in, don't lose it!"),
+                            span((
+                                span("you can always see your secret in "),
+                                a.class("underline").href(Route::Profile)("profile"),
+                            )),
+                        )),
+                        p("this message will self-destruct in 60s"),
+                    )),
+                ),
+                div.id("set-results")((
+                    render_if(
+                        filtered,
+                        div.class("flex justify-between dark:text-gray-400 text-gray-500")((
+                            span(match count {
+                                0 => "no sets match".to_owned(),
+                                1 => "1 set".to_owned(),
+                                _ if count >= SEARCH_LIMIT => format!("latest {} sets", count),
+                                _ => format!("{} sets", count),
+                            }),
+                            a.class("underline").href(Route::SetList)("clear"),
+                        )),
+                    ),
+                    ul.class("divide-y divide-gray-100 dark:divide-gray-800")(raw(items.concat())),
+                )),
+                div.class("invisible lg:visible")(link_button().href(Route::Root)(
+                    "start another set",
+                )),
+                undo_toasts(toasts(), deleted),
+            ))
+        }
+
+        /// Plain get form so it works unboosted, htmx swaps just the results
+        /// and pushes the query string so the url stays shareable
+        fn set_filter_part(filter: SetFilter) -> impl Render {
+            let SetFilter {
+                name,
+                from,
+                to,
+                min_weight,
+                set_type,
+            } = filter;
+            let set_types = SetType::ALL
+                .into_iter()
+                .map(|value| {
+                    let tag = option.value(value.value());
+                    let tag = match Some(value) == set_type {
+                        true => tag.attr1("selected"),
+                        false => tag,
+                    };
+                    tag(value.label())
+                })
+                .collect::<Vec<_>>();
+            let mut more = details.class("flex flex-col");
+            if from.is_some() || to.is_some() || min_weight.is_some() || set_type.is_some() {
+                more = more.attr1("open");
+            }
+            html::form
+                .method("get")
+                .action(Route::SetList)
+                .class("flex flex-col gap-4")
+                .attr("hx-get", Route::SetList)
+                .attr(
+                    "hx-trigger",
+                    "input changed delay:300ms from:#search, change",
+                )
+                .attr("hx-target", "#set-results")
+                .attr("hx-select", "#set-results")
+                .attr("hx-swap", "outerHTML")
+                .attr("hx-push-url", "true")((
+                text_input()
+                    .r#type("search")
+                    .name("name")
+                    .id("search")
+                    .attr("placeholder", "search exercises")
+                    .value(name.unwrap_or_default()),
+                more((
+                    summary.class("dark:text-gray-400 text-gray-500 cursor-pointer")("filter"),
+                    div.class("flex flex-col gap-4 pt-4")((
+                        div.class("flex gap-4")((
+                            div.class("w-full")((
+                                label("from"),
+                                input
+                                    .class(INPUT_CLASS)
+                                    .r#type("date")
+                                    .name("from")
+                                    .id("from")
+                                    .value(from.unwrap_or_default()),
+                            )),
+                            div.class("w-full")((
+                                label("to"),
+                                input
+                                    .class(INPUT_CLASS)
+                                    .r#type("date")
+                                    .name("to")
+                                    .id("to")
+                                    .value(to.unwrap_or_default()),
+                            )),
+                        )),
+                        div.class("flex gap-4")((
+                            div.class("w-full")((
+                                html::label.class("flex flex-col gap-1").r#for("min_weight")(
+                                    "min weight",
+                                ),
+                                input
+                                    .class(INPUT_CLASS)
+                                    .r#type("number")
+                                    .name("min_weight")
+                                    .id("min_weight")
+                                    .value(min_weight.unwrap_or_default()),
+                            )),
+                            div.class("w-full")((
+                                html::label.class("flex flex-col gap-1").r#for("set_type")(
+                                    "set type",
+                                ),
+                                select.class(INPUT_CLASS).name("set_type").id("set_type")((
+                                    option.value("")("any"),
+                                    set_types,
+                                )),
+                            )),
+                        )),
+                    )),
+                )),
+            ))
+        }
+
+        /// Out of band swaps replace this by id, see `deleted_set_part`
+        fn toasts() -> Tag {
+            div.id("toasts")
+        }
+
+        fn undo_toasts(toasts: Tag, deleted: Option<Set>) -> impl Render {
+            toasts(raw(deleted
+                .map(|set| undo_toast(set).render_to_string())
+                .unwrap_or_default()))
+        }
+
+        /// Swapped over the deleted `li`, leaving nothing behind but the
+        /// undo toast
+        pub fn deleted_set_part(set: Set) -> impl Render {
+            undo_toasts(toasts().attr("hx-swap-oob", "true"), Some(set))
+        }
+
+        pub fn link_button() -> Tag {
+            a.class("flex rounded-md bg-orange-500 active:bg-orange-700 text-white p-4 items-center justify-center uppercase w-full")
+        }
+
+        pub fn button() -> Tag {
+            html::button.class("flex rounded-md bg-orange-500 active:bg-orange-700 text-white p-4 items-center justify-center uppercase w-full")
+        }
+
+        fn head() -> impl Render {
+            let static_files = StaticFile::once();
+            html::head((
+                link.href(static_files.tailwind.clone()).rel("stylesheet"),
+                script.src(static_files.htmx.clone()).defer(),
+                script.src(static_files.json_enc.clone()).defer(),
+                script.src(static_files.preload.clone()).defer(),
+                script.src(static_files.rest_timer.clone()).defer(),
+                script.src(static_files.time_zone.clone()).defer(),
+                meta.name("htmx-config")
+                    .content(r#"{"includeIndicatorStyles":false}"#),
+                meta.charset("UTF-8"),
+                meta.content("text/html; charset=utf-8")
+                    .attr("http-equiv", "Content-Type"),
+                meta.name("viewport")
+                    .content("width=device-width, initial-scale=1, user-scalable=no"),
+            ))
+        }
+
+        fn plus_circle_icon() -> impl Render {
+            raw(r#"
+            <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
+              <path stroke-linecap="round" stroke-linejoin="round" d="M12 9v6m3-3H9m12 0a9 9 0 1 1-18 0 9 9 0 0 1 18 0Z" />
+            </svg>
+        "#)
+        }
+
+        fn list_icon() -> impl Render {
+            raw(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
+  <path stroke-linecap="round" stroke-linejoin="round" d="M8.25 6.75h12M8.25 12h12m-12 5.25h12M3.75 6.75h.007v.008H3.75V6.75Zm.375 0a.375.375 0 1 1-.75 0 .375.375 0 0 1 .75 0ZM3.75 12h.007v.008H3.75V12Zm.375 0a.375.375 0 1 1-.75 0 .375.375 0 0 1 .75 0Zm-.375 5.25h.007v.008H3.75v-.008Zm.375 0a.375.375 0 1 1-.75 0 .375.375 0 0 1 .75 0Z" />
+</svg>
+"#,
+            )
+        }
+
+        fn chart_bar_icon() -> impl Render {
+            raw(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
+  <path stroke-linecap="round" stroke-linejoin="round" d="M3 13.125C3 12.504 3.504 12 4.125 12h2.25c.621 0 1.125.504 1.125 1.125v6.75C7.5 20.496 6.996 21 6.375 21h-2.25A1.125 1.125 0 0 1 3 19.875v-6.75ZM9.75 8.625c0-.621.504-1.125 1.125-1.125h2.25c.621 0 1.125.504 1.125 1.125v11.25c0 .621-.504 1.125-1.125 1.125h-2.25a1.125 1.125 0 0 1-1.125-1.125V8.625ZM16.5 4.125c0-.621.504-1.125 1.125-1.125h2.25C20.496 3 21 3.504 21 4.125v15.75c0 .621-.504 1.125-1.125 1.125h-2.25a1.125 1.125 0 0 1-1.125-1.125V4.125Z" />
+</svg>
+"#,
+            )
+        }
+
+        fn trending_up_icon() -> impl Render {
+            raw(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
+  <path stroke-linecap="round" stroke-linejoin="round" d="M2.25 18 9 11.25l4.306 4.306a11.95 11.95 0 0 1 5.814-5.518l2.74-1.22m0 0-5.94-2.281m5.94 2.28-2.28 5.941" />
+</svg>
+"#,
+            )
+        }
+
+        fn user_circle_icon() -> impl Render {
+            raw(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
+  <path stroke-linecap="round" stroke-linejoin="round" d="M17.982 18.725A7.488 7.488 0 0 0 12 15.75a7.488 7.488 0 0 0-5.982 2.975m11.963 0a9 9 0 1 0-11.963 0m11.963 0A8.966 8.966 0 0 1 12 21a8.966 8.966 0 0 1-5.982-2.275M15 9.75a3 3 0 1 1-6 0 3 3 0 0 1 6 0Z" />
+</svg>
+"#,
+            )
+        }
+
+        fn nav_link(
+            route: Route,
+            current_route: Route,
+            icon: impl Render,
+            s: &'static str,
+        ) -> impl Render {
+            let mut class = "flex flex-col text-center justify-center items-center".to_owned();
+            if route == current_route {
+                class.push_str(" text-orange-500");
+            }
+            a.class(class).attr1("preload").href(route)((
+                span.class("visible lg:invisible")(icon),
+                span(s),
+            ))
+        }
+
+        fn nav(route: Route) -> impl Render {
+            html::nav.class(
+            "text-center lg:max-w-md w-full dark:bg-gray-800 bg-gray-300 lg:bg-transparent lg:dark:bg-transparent flex lg:mx-auto justify-around items-center lg:py-6 py-2 absolute bottom-0 lg:bottom-auto lg:relative",
+        )((
+            nav_link(Route::SetList, route, list_icon(), "Sets"),
+            nav_link(Route::SetForm, route, plus_circle_icon(), "Add a set"),
+            nav_link(Route::Stats, route, chart_bar_icon(), "Stats"),
+            nav_link(Route::Measurements, route, trending_up_icon(), "Body"),
+            nav_link(Route::Profile, route, user_circle_icon(), "Profile"),
+        ))
+        }
+
+        fn body(route: Route, inner: impl Render) -> impl Render {
+            html::body
+                .class("h-screen dark:bg-gray-900 dark:text-white")
+                .attr("hx-boost", "true")
+                .attr("hx-push-url", "true")
+                .attr("hx-ext", "json-enc, preload")((
+                nav(route),
+                html::main.class("max-w-lg mx-auto lg:mt-16 pt-4")(inner),
+            ))
+        }
+
+        pub fn render(route: Route, inner: impl Render) -> Result<Responder> {
+            Ok(response(route, inner))
+        }
+
+        pub fn response(route: Route, inner: impl Render) -> Responder {
+            res().render((doctype("html"), html((head(), body(route, inner)))))
+        }
+
+        fn label(name: &'static str) -> impl Render {
+            html::label.class("flex flex-col gap-1").r#for(name)(name)
+        }
+
+        fn text_input() -> Tag {
+            input.class("block w-full rounded-md border-0 px-2 py-4 dark:bg-gray-700 dark:text-white light:text-gray-900 outline-0 focus:outline-0 focus:ring-0 focus-visible:outline-0 focus:outline-none placeholder:text-gray-400").r#type("text")
+        }
+
+        const INPUT_CLASS: &str = "block w-full rounded-md border-0 px-2 py-4 dark:bg-gray-700 dark:text-white light:text-gray-900 outline-0 focus:outline-0 focus:ring-0 focus-visible:outline-0 focus:outline-none placeholder:text-gray-400";
+
+        fn number_input(name: &'static str, value: impl std::fmt::Display) -> impl Render {
+            input
+                .class(INPUT_CLASS)
+                .r#type("number")
+                .attr1("data-number")
+                .name(name)
+                .value(value.to_string())
+        }
+
+        /// Any step so fractional plates like 1.25 can be logged
+        fn weight_input(name: &'static str, value: f64) -> impl Render {
+            input
+                .class(INPUT_CLASS)
+                .r#type("number")
+                .attr("step", "any")
+                .attr1("data-number")
+                .name(name)
+                .value(value.to_string())
+        }
+
+        fn optional_number_input(name: &'static str, value: Option<usize>) -> impl Render {
+            // used for time and distance too, so no upper bound
+            input
+                .class(INPUT_CLASS)
+                .r#type("number")
+                .attr("min", "0")
+                .attr1("data-number")
+                .name(name)
+                .id(name)
+                .value(value.map(|value| value.to_string()).unwrap_or_default())
+        }
+
+        fn suggest_input(
+            text_input: Tag,
+            name: &'static str,
+            options: Vec<String>,
+            value: String,
+            autofocus: bool,
+        ) -> impl Render {
+            let list = format!("{}_list", name);
+            let mut text_input = text_input
+                .name(name)
+                .value(value)
+                .id(name)
+                .attr("list", list.clone());
+            if autofocus {
+                text_input = text_input.attr("autofocus", "autofocus")
+            }
+            (
+                text_input,
+                datalist.id(list)(
+                    options
+                        .into_iter()
+                        .map(|s| option.value(s))
+                        .collect::<Vec<_>>(),
+                ),
+            )
+        }
+
+        pub fn profile_part(
+            user: User,
+            bodyweight: Option<f64>,
+            error: Option<String>,
+        ) -> impl Render {
+            div.class("flex flex-col gap-8 px-4 lg:px-0")((
+                h1.class("text-2xl text-center")("Profile"),
+                h1(("Your secret key: ", span.class("font-bold")(user.secret))),
+                p("don't lose this, it's your only way back to your sets"),
+                form(Route::CreateMeasurement).class("flex flex-col gap-1")((
+                    html::label.r#for("value")("bodyweight"),
+                    hidden_input()
+                        .name("kind")
+                        .value(MeasurementKind::Bodyweight.value()),
+                    div.class("flex gap-4 items-center")((
+                        input
+                            .class(INPUT_CLASS)
+                            .r#type("number")
+                            .attr("step", "0.1")
+                            .attr("min", "0")
+                            .attr1("data-number")
+                            .name("value")
+                            .id("value")
+                            .value(
+                                bodyweight
+                                    .map(|value| value.to_string())
+                                    .unwrap_or_default(),
+                            ),
+                        small_button()("Log"),
+                    )),
+                    p.class("text-sm dark:text-gray-400 text-gray-500")(
+                        "bodyweight exercises add this to the weight you log",
+                    ),
+                )),
+                form(Route::UpdateTimeZone).class("flex flex-col gap-1")((
+                    html::label.r#for("time_zone")("time zone"),
+                    div.class("flex gap-4 items-center")((
+                        time_zone_select(user.tz()),
+                        small_button()("Save"),
+                    )),
+                    p.class("text-sm dark:text-gray-400 text-gray-500")(
+                        "sets are grouped by day on this clock",
+                    ),
+                    p.class("text-sm text-red-500")(error.unwrap_or_default()),
+                )),
+                a.class("underline").href(Route::Exercises)("exercises"),
+                a.class("underline").href(Route::Templates)("templates"),
+                a.class("underline").href(Route::Plates)("plate calculator"),
+                form(Route::Logout)(button()("logout")),
+                div.class("flex justify-between")((
+                    a.class("underline")
+                        .href(Route::Export)
+                        .attr("hx-boost", "false")
+                        .attr1("download")("export my data"),
+                    a.class("underline text-red-500")
+                        .href(Route::DeleteAccountForm)("delete my account"),
+                )),
+            ))
+        }
+
+        fn time_zone_select(value: Tz) -> impl Render {
+            let options = chrono_tz::TZ_VARIANTS
+                .into_iter()
+                .map(|tz| {
+                    let tag = option.value(tz.name());
+                    let tag = match tz == value {
+                        true => tag.attr1("selected"),
+                        false => tag,
+                    };
+                    tag(tz.name())
+                })
+                .collect::<Vec<_>>();
+
+            select.class(INPUT_CLASS).name("time_zone").id("time_zone")(options)
+        }
+
+        pub fn measurements_part(
+            kind: MeasurementKind,
+            measurements: Vec<Measurement>,
+            tz: Tz,
+        ) -> impl Render {
+            div.class("flex flex-col gap-8 px-4 lg:px-0")((
+                h1.class("text-2xl text-center")("Body"),
+                div.class("flex gap-4 overflow-x-auto")(
+                    MeasurementKind::ALL
+                        .into_iter()
+                        .map(|tab| {
+                            let class = match tab == kind {
+                                true => "text-orange-500 whitespace-nowrap",
+                                false => "whitespace-nowrap",
+                            };
+                            a.class(class).href(format!(
+                                "{}?kind={}",
+                                Route::Measurements,
+                                tab.value()
+                            ))(tab.label())
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+                form(Route::CreateMeasurement).class("flex flex-col gap-4")((
+                    hidden_input().name("kind").value(kind.value()),
+                    div.class("flex gap-4")((
+                        div.class("w-full")((
+                            html::label.class("flex flex-col gap-1").r#for("value")(format!(
+                                "{} ({})",
+                                kind.label(),
+                                kind.unit()
+                            )),
+                            input
+                                .class(INPUT_CLASS)
+                                .r#type("number")
+                                .attr("step", "0.1")
+                                .attr("min", "0")
+                                .attr1("required")
+                                .attr1("data-number")
+                                .name("value")
+                                .id("value"),
+                        )),
+                        div.class("w-full")((
+                            label("date"),
+                            input
+                                .class(INPUT_CLASS)
+                                .r#type("date")
+                                .name("date")
+                                .id("date")
+                                .value(date::format(date::today(tz))),
+                        )),
+                    )),
+                    button()("save"),
+                )),
+                trend_chart(&measurements),
+                ul.class("divide-y divide-gray-100 dark:divide-gray-800")(
+                    measurements
+                        .into_iter()
+                        .rev()
+                        .map(|measurement| {
+                            li.class("flex justify-between py-4")((
+                                span(date::format(date::day(measurement.measured_at, tz))),
+                                span.class("font-bold")(format!(
+                                    "{} {}",
+                                    measurement.value,
+                                    measurement.kind.unit()
+                                )),
+                            ))
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            ))
+        }
+
+        /// Every entry as a dot with a 7 day moving average drawn through
+        /// them, so a bad weigh-in doesn't look like a trend
+        fn trend_chart(measurements: &[Measurement]) -> impl Render {
+            const WIDTH: f64 = 400.0;
+            const HEIGHT: f64 = 160.0;
+            const PAD: f64 = 8.0;
+
+            if measurements.len() < 2 {
+                return raw(p.class("text-center dark:text-gray-400 text-gray-500")(
+                    "log a couple of entries to see a trend",
+                )
+                .render_to_string());
+            }
+            let first = measurements[0].measured_at as f64;
+            let last = measurements[measurements.len() - 1].measured_at as f64;
+            let (low, high) = measurements
+                .iter()
+                .fold((f64::MAX, f64::MIN), |(low, high), m| {
+                    (low.min(m.value), high.max(m.value))
+                });
+            let x = |time: u64| {
+                PAD + (time as f64 - first) / (last - first).max(1.0) * (WIDTH - PAD * 2.0)
+            };
+            let y = |value: f64| {
+                HEIGHT - PAD - (value - low) / (high - low).max(0.1) * (HEIGHT - PAD * 2.0)
+            };
+            let dots = measurements
+                .iter()
+                .map(|m| {
+                    format!(
+                        r#"<circle cx="{:.1}" cy="{:.1}" r="2.5" fill="currentColor" opacity="0.5"/>"#,
+                        x(m.measured_at),
+                        y(m.value)
+                    )
+                })
+                .collect::<String>();
+            let average = moving_average(measurements, 7 * date::DAY)
+                .into_iter()
+                .map(|(time, value)| format!("{:.1},{:.1}", x(time), y(value)))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            raw(format!(
+                r#"<div class="flex flex-col gap-1"><div class="flex justify-between text-sm dark:text-gray-400 text-gray-500"><span>{high}</span><span>7 day average</span></div><svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {HEIGHT}" class="w-full h-40">{dots}<polyline points="{average}" fill="none" stroke="rgb(249 115 22)" stroke-width="2" stroke-linejoin="round"/></svg><div class="text-sm dark:text-gray-400 text-gray-500">{low}</div></div>"#
+            ))
+        }
+
+        /// The mean of every entry within `window` seconds before each one
+        fn moving_average(measurements: &[Measurement], window: u64) -> Vec<(u64, f64)> {
+            measurements
+                .iter()
+                .map(|m| {
+                    let recent = measurements
+                        .iter()
+                        .filter(|other| {
+                            other.measured_at <= m.measured_at
+                                && m.measured_at - other.measured_at < window
+                        })
+                        .map(|other| other.value)
+                        .collect::<Vec<_>>();
+                    (
+                        m.measured_at,
+                        recent.iter().sum::<f64>() / recent.len() as f64,
+                    )
+                })
+                .collect()
+        }
+
+        pub fn delete_account_part(form: DeleteAccountForm) -> impl Render {
+            self::form(Route::DeleteAccount).class("flex flex-col gap-4 px-4 lg:px-0")((
+                h1.class("text-2xl text-center")("Delete your account"),
+                p("this removes you, your sessions and every set you've logged. it can't be undone, so export your data first if you want to keep it."),
+                div(form.error.unwrap_or_default()),
+                div.class("flex flex-col gap-1")((
+                    label("type delete to confirm"),
+                    text_input()
+                        .attr("autocomplete", "off")
+                        .name("confirm")
+                        .value(form.confirm),
+                )),
+                button()("delete my account"),
+            ))
+        }
+
+        fn form(route: Route) -> Tag {
+            html::form.method("post").action(route)
+        }
+
+        pub fn error_part(status_code: StatusCode, message: impl Render) -> impl Render {
+            div.class("flex flex-col gap-8 px-4 lg:px-0 text-center")((
+                h1.class("text-2xl")(status_code.as_u16().to_string()),
+                p(message),
+                a.class("underline").href(Route::Root)("go back home"),
+            ))
+        }
+
+        pub fn login_form_part(login_form: LoginForm) -> impl Render {
+            form(Route::Login).class("flex flex-col gap-4 px-4 lg:px-0")((
+                div(match login_form.error {
+                    Some(err) => err,
+                    None => "".into(),
+                }),
+                div.class("flex flex-col gap-1")((
+                    label("Enter your secret"),
+                    text_input()
+                        .attr("autofocus", "autofocus")
+                        .name("secret")
+                        .value(login_form.secret),
+                )),
+                button()("login"),
+            ))
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Default, Clone)]
+    struct SetForm {
+        name: String,
+        #[serde(default)]
+        reps: usize,
+        #[serde(default)]
+        weight: f64,
+        #[serde(default, deserialize_with = "de::optional")]
+        duration: Option<usize>,
+        #[serde(default, deserialize_with = "de::optional")]
+        distance: Option<usize>,
+        #[serde(default, deserialize_with = "de::optional")]
+        rpe: Option<f64>,
+        #[serde(default, deserialize_with = "de::optional")]
+        rir: Option<usize>,
+        #[serde(default, deserialize_with = "de::optional")]
+        notes: Option<String>,
+        #[serde(default)]
+        set_type: SetType,
+        #[serde(default, deserialize_with = "de::optional")]
+        template: Option<String>,
+        #[serde(default)]
+        step: usize,
+        /// filled in by the browser, only read when the set makes a new user
+        #[serde(default, deserialize_with = "de::optional")]
+        time_zone: Option<String>,
+        /// yyyy-mm-ddThh:mm in the user's zone, blank means now
+        #[serde(default, deserialize_with = "de::optional")]
+        performed_at: Option<String>,
+    }
+
+    /// Repeats a set, leaving out how it felt since that's for the next one
+    impl From<Set> for SetForm {
+        fn from(set: Set) -> Self {
+            Self {
+                name: set.name,
+                reps: set.reps,
+                weight: set.weight,
+                duration: set.duration,
+                distance: set.distance,
+                set_type: set.set_type,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// json-enc sends `data-number` inputs as numbers and leaves blank
+    /// inputs as "", these take either for optional fields
+    mod de {
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer};
+        use std::str::FromStr;
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            String(String),
+            Number(f64),
+        }
+
+        /// Blank is none, anything else goes through `FromStr`. numbers are
+        /// only sent as json numbers for inputs marked `data-number`
+        pub fn optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            D: Deserializer<'de>,
+            T: FromStr,
+        {
+            let value = match Option::<Value>::deserialize(deserializer)? {
+                Some(Value::String(s)) => s.trim().to_owned(),
+                Some(Value::Number(n)) => n.to_string(),
+                None => return Ok(None),
+            };
+            match value.is_empty() {
+                true => Ok(None),
+                false => value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| D::Error::custom(format!("{} is not valid here", value))),
+            }
+        }
+    }
+
+    /// Calendar days in a user's time zone. Days are counted from
+    /// 1970-01-01 on the local calendar, so they compare without a zone
+    mod date {
+        use super::now;
+        use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Offset, TimeZone, Timelike};
+        pub use chrono_tz::Tz;
+
+        pub const DAY: u64 = 86_400;
+        /// 1970-01-01 counted from 0001-01-01 the way chrono does
+        const EPOCH: i64 = 719_163;
+
+        /// Seconds the zone is ahead of utc at a unix time
+        pub fn offset(seconds: u64, tz: Tz) -> i64 {
+            let utc = DateTime::from_timestamp(seconds as i64, 0)
+                .unwrap_or_default()
+                .naive_utc();
+
+            tz.offset_from_utc_datetime(&utc).fix().local_minus_utc() as i64
+        }
+
+        /// The local day a unix time falls on
+        pub fn day(seconds: u64, tz: Tz) -> i64 {
+            (seconds as i64 + offset(seconds, tz)).div_euclid(DAY as i64)
+        }
+
+        pub fn today(tz: Tz) -> i64 {
+            day(now(), tz)
+        }
+
+        /// The unix time of local midnight at the start of a day
+        pub fn start_of(day: i64, tz: Tz) -> u64 {
+            from_local(day * DAY as i64, tz)
+        }
+
+        /// The unix time of a local clock reading in seconds
+        fn from_local(local: i64, tz: Tz) -> u64 {
+            // guess with the offset at the same clock time in utc, then once
+            // more with the offset at the guess, which settles around dst
+            let guess = local - offset(local.max(0) as u64, tz);
+
+            (local - offset(guess.max(0) as u64, tz)).max(0) as u64
+        }
+
+        /// Monday is 0, the unix epoch was a thursday
+        pub fn weekday(day: i64) -> i64 {
+            (day + 3).rem_euclid(7)
+        }
+
+        pub fn monday(day: i64) -> i64 {
+            day - weekday(day)
+        }
+
+        /// The calendar date of a local day
+        pub fn naive(day: i64) -> NaiveDate {
+            i32::try_from(day + EPOCH)
+                .ok()
+                .and_then(NaiveDate::from_num_days_from_ce_opt)
+                .unwrap_or_default()
+        }
+
+        /// A yyyy-mm-dd day, impossible dates like 2024-02-31 are none
+        pub fn parse(s: &str) -> Option<i64> {
+            let date = NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()?;
+
+            Some(date.num_days_from_ce() as i64 - EPOCH)
+        }
+
+        pub fn format(day: i64) -> String {
+            naive(day).format("%Y-%m-%d").to_string()
+        }
+
+        /// A yyyy-mm-ddThh:mm local time from a datetime-local input, a
+        /// space works in place of the T
+        pub fn parse_time(s: &str, tz: Tz) -> Option<u64> {
+            let (day, time) = s.trim().split_once(['T', ' '])?;
+            let day = parse(day)?;
+            // datetime-local can send seconds too, they're dropped
+            let time = NaiveTime::parse_from_str(time.get(..5)?, "%H:%M").ok()?;
+
+            Some(from_local(
+                day * DAY as i64 + time.num_seconds_from_midnight() as i64,
+                tz,
+            ))
+        }
+
+        /// yyyy-mm-dd hh:mm on the local clock
+        pub fn format_time(seconds: u64, tz: Tz) -> String {
+            format_local(seconds, tz, ' ')
+        }
+
+        /// The value a datetime-local input expects
+        pub fn format_input(seconds: u64, tz: Tz) -> String {
+            format_local(seconds, tz, 'T')
+        }
+
+        fn format_local(seconds: u64, tz: Tz, separator: char) -> String {
+            DateTime::from_timestamp(seconds as i64, 0)
+                .unwrap_or_default()
+                .with_timezone(&tz)
+                .format(&format!("%Y-%m-%d{}%H:%M", separator))
+                .to_string()
+        }
+    }
+
+    /// Which plates go on each side of the bar, heaviest first
+    mod plates {
+        /// Slack for plates like 2.5 and 1.25 that don't add up exactly in
+        /// floating point
+        const EPSILON: f64 = 1e-6;
+
+        #[derive(Clone, Copy, PartialEq, Debug)]
+        pub struct Plate {
+            pub weight: f64,
+            /// plates owned, none for as many as it takes
+            pub count: Option<usize>,
+        }
+
+        pub struct Loading {
+            pub per_side: Vec<f64>,
+            /// what the plates couldn't make, both sides together
+            pub missing: f64,
+        }
+
+        /// Plates separated by commas or spaces, `45x4` for a weight you
+        /// have four of
+        pub fn parse(s: &str) -> Option<Vec<Plate>> {
+            let mut plates = vec![];
+            for part in s.split([',', ' ']).filter(|part| !part.trim().is_empty()) {
+                let (weight, count) = match part.trim().split_once('x') {
+                    Some((weight, count)) => (weight, Some(count.parse().ok()?)),
+                    None => (part.trim(), None),
+                };
+                let weight: f64 = weight.parse().ok()?;
+                if !weight.is_finite() || weight <= 0.0 {
+                    return None;
+                }
+                plates.push(Plate { weight, count });
+            }
+            plates.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+            match plates.is_empty() {
+                true => None,
+                false => Some(plates),
+            }
+        }
+
+        /// The smallest jump the plates can make, a pair of the lightest
+        /// ones there are two of
+        pub fn step(plates: &[Plate]) -> Option<f64> {
+            plates
+                .iter()
+                .filter(|plate| plate.count.unwrap_or(2) >= 2)
+                .map(|plate| plate.weight * 2.0)
+                .last()
+        }
+
+        pub fn format(plates: &[Plate]) -> String {
+            plates
+                .iter()
+                .map(|plate| match plate.count {
+                    Some(count) => format!("{}x{}", plate.weight, count),
+                    None => plate.weight.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        /// None when the weight is lighter than the bar
+        pub fn load(weight: f64, bar: f64, plates: &[Plate]) -> Option<Loading> {
+            if weight + EPSILON < bar {
+                return None;
+            }
+            let mut side = (weight - bar) / 2.0;
+            let mut per_side = vec![];
+            for plate in plates {
+                // plates go on in pairs, an odd one out stays on the rack
+                let mut pairs = plate.count.map(|count| count / 2);
+                while side + EPSILON >= plate.weight && pairs != Some(0) {
+                    side -= plate.weight;
+                    per_side.push(plate.weight);
+                    pairs = pairs.map(|pairs| pairs - 1);
+                }
+            }
+            let missing = match side * 2.0 {
+                missing if missing < EPSILON => 0.0,
+                missing => missing,
+            };
+
+            Some(Loading { per_side, missing })
+        }
+    }
+
+    /// Works out today's targets for a template exercise from the sets
+    /// logged for it before today
+    mod program {
+        use super::date::{self, Tz};
+        use super::db::{Program, Set, SetType, TemplateExercise};
+
+        pub struct Prescription {
+            pub exercise: TemplateExercise,
+            pub targets: Vec<Target>,
+            pub note: String,
+        }
+
+        #[derive(Clone, Copy)]
+        pub struct Target {
+            pub reps: usize,
+            pub weight: f64,
+            pub set_type: SetType,
+        }
+
+        /// Missed sessions at the same weight before a linear deload
+        const FAILURES: usize = 3;
+
+        /// Percentage of training max and reps for each week of a 5/3/1
+        /// cycle, the last set of the first three weeks is as many reps as
+        /// possible
+        const WAVES: [[(f64, usize); 3]; 4] = [
+            [(0.65, 5), (0.75, 5), (0.85, 5)],
+            [(0.70, 3), (0.80, 3), (0.90, 3)],
+            [(0.75, 5), (0.85, 3), (0.95, 1)],
+            [(0.40, 5), (0.50, 5), (0.60, 5)],
+        ];
+
+        pub fn prescribe(
+            program: Program,
+            exercise: TemplateExercise,
+            history: &[Set],
+            tz: Tz,
+            step: Option<f64>,
+        ) -> Prescription {
+            let sessions = sessions(history, tz);
+            let (targets, note) = match program {
+                Program::Fixed => (straight_sets(&exercise, exercise.weight), String::new()),
+                Program::Linear => {
+                    let (weight, note) = linear(&exercise, &sessions, step);
+                    (straight_sets(&exercise, weight), note)
+                }
+                Program::FiveThreeOne => five_three_one(&exercise, sessions.len(), step),
+            };
+
+            Prescription {
+                exercise,
+                targets,
+                note,
+            }
+        }
+
+        fn straight_sets(exercise: &TemplateExercise, weight: f64) -> Vec<Target> {
+            vec![
+                Target {
+                    reps: exercise.reps,
+                    weight,
+                    set_type: SetType::Working,
+                };
+                exercise.sets
+            ]
+        }
+
+        /// Working sets grouped by the local day they were logged, oldest
+        /// first
+        fn sessions(history: &[Set], tz: Tz) -> Vec<Vec<&Set>> {
+            let mut sessions: Vec<Vec<&Set>> = vec![];
+            for set in history.iter().filter(|set| set.set_type.counts()) {
+                let day = date::day(set.performed_at, tz);
+                match sessions.last_mut() {
+                    Some(session) if date::day(session[0].performed_at, tz) == day => {
+                        session.push(set)
+                    }
+                    _ => sessions.push(vec![set]),
+                }
+            }
+
+            sessions
+        }
+
+        fn top_weight(session: &[&Set]) -> f64 {
+            session.iter().map(|set| set.weight).fold(0.0, f64::max)
+        }
+
+        /// Every target set done for the target reps at the session's top weight
+        fn passed(exercise: &TemplateExercise, session: &[&Set]) -> bool {
+            let weight = top_weight(session);
+            session
+                .iter()
+                .filter(|set| set.weight >= weight && set.reps >= exercise.reps)
+                .count()
+                >= exercise.sets
+        }
+
+        fn linear(
+            exercise: &TemplateExercise,
+            sessions: &[Vec<&Set>],
+            step: Option<f64>,
+        ) -> (f64, String) {
+            let Some(last) = sessions.last() else {
+                return (exercise.weight, "first session, starting weight".to_owned());
+            };
+            let weight = top_weight(last);
+            if passed(exercise, last) {
+                return (
+                    weight + exercise.increment,
+                    format!("every rep last time, +{} lbs", exercise.increment),
+                );
+            }
+            let failures = sessions
+                .iter()
+                .rev()
+                .take_while(|session| top_weight(session) == weight && !passed(exercise, session))
+                .count();
+            match failures >= FAILURES {
+                true => (
+                    round(weight * 0.9, step),
+                    format!("missed {} sessions in a row, deload 10%", failures),
+                ),
+                false => (weight, "missed reps last time, same weight".to_owned()),
+            }
+        }
+
+        /// The template weight is the starting training max, it goes up by
+        /// the increment after every four week cycle
+        fn five_three_one(
+            exercise: &TemplateExercise,
+            sessions: usize,
+            step: Option<f64>,
+        ) -> (Vec<Target>, String) {
+            let week = sessions % WAVES.len();
+            let cycle = sessions / WAVES.len();
+            let training_max = exercise.weight + cycle as f64 * exercise.increment;
+            let deload = week == WAVES.len() - 1;
+            let targets = WAVES[week]
+                .iter()
+                .enumerate()
+                .map(|(index, (percent, reps))| Target {
+                    reps: *reps,
+                    weight: round(training_max * percent, step),
+                    set_type: match !deload && index == WAVES[week].len() - 1 {
+                        true => SetType::Amrap,
+                        false => SetType::Working,
+                    },
+                })
+                .collect();
+            let note = format!(
+                "cycle {} week {}{}, training max {} lbs",
+                cycle + 1,
+                week + 1,
+                if deload { " (deload)" } else { "" },
+                training_max
+            );
+
+            (targets, note)
+        }
+
+        /// To the nearest jump the lifter's plates can load, left alone when
+        /// they can't make any
+        fn round(weight: f64, step: Option<f64>) -> f64 {
+            match step {
+                Some(step) => (weight / step).round() * step,
+                None => weight,
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            fn squat(weight: f64) -> TemplateExercise {
+                TemplateExercise {
+                    id: "1".into(),
+                    user_id: "1".into(),
+                    template_id: "1".into(),
+                    position: 0,
+                    name: "squat".into(),
+                    sets: 3,
+                    reps: 5,
+                    weight,
+                    created_at: 0,
+                    increment: 5.0,
+                }
+            }
+
+            fn set(performed_at: u64, weight: f64, reps: usize) -> Set {
+                Set {
+                    id: performed_at.to_string(),
+                    user_id: "1".into(),
+                    name: "squat".into(),
+                    weight,
+                    reps,
+                    created_at: performed_at,
+                    deleted_at: None,
+                    rpe: None,
+                    rir: None,
+                    notes: None,
+                    set_type: SetType::Working,
+                    duration: None,
+                    distance: None,
+                    performed_at,
+                }
+            }
+
+            /// Three sets on each day, the last set of a day gets `last_reps`
+            fn history(days: &[(f64, usize)]) -> Vec<Set> {
+                days.iter()
+                    .enumerate()
+                    .flat_map(|(day, (weight, last_reps))| {
+                        let start = day as u64 * date::DAY + 12 * 3600;
+                        [
+                            set(start, *weight, 5),
+                            set(start + 300, *weight, 5),
+                            set(start + 600, *weight, *last_reps),
+                        ]
+                    })
+                    .collect()
+            }
+
+            fn weights(prescription: &Prescription) -> Vec<f64> {
+                prescription
+                    .targets
+                    .iter()
+                    .map(|target| target.weight)
+                    .collect()
+            }
+
+            #[test]
+            fn linear_starts_at_the_template_weight() {
+                let prescription =
+                    prescribe(Program::Linear, squat(135.0), &[], Tz::UTC, Some(5.0));
+
+                assert_eq!(weights(&prescription), vec![135.0, 135.0, 135.0]);
+            }
+
+            #[test]
+            fn linear_adds_the_increment_after_every_rep() {
+                let history = history(&[(135.0, 5)]);
+                let prescription =
+                    prescribe(Program::Linear, squat(135.0), &history, Tz::UTC, Some(5.0));
+
+                assert_eq!(weights(&prescription), vec![140.0, 140.0, 140.0]);
+            }
+
+            #[test]
+            fn linear_repeats_the_weight_after_missed_reps() {
+                let history = history(&[(135.0, 5), (140.0, 3)]);
+                let prescription =
+                    prescribe(Program::Linear, squat(135.0), &history, Tz::UTC, Some(5.0));
+
+                assert_eq!(weights(&prescription), vec![140.0, 140.0, 140.0]);
+            }
+
+            #[test]
+            fn linear_deloads_after_three_misses_at_the_same_weight() {
+                let history = history(&[(200.0, 3), (200.0, 4), (200.0, 3)]);
+                let prescription =
+                    prescribe(Program::Linear, squat(135.0), &history, Tz::UTC, Some(5.0));
+
+                assert_eq!(weights(&prescription), vec![180.0, 180.0, 180.0]);
+                assert!(prescription.note.contains("deload"));
+            }
+
+            #[test]
+            fn linear_only_counts_misses_at_the_current_weight() {
+                let history = history(&[(200.0, 3), (200.0, 5), (205.0, 3), (205.0, 3)]);
+                let prescription =
+                    prescribe(Program::Linear, squat(135.0), &history, Tz::UTC, Some(5.0));
+
+                assert_eq!(weights(&prescription), vec![205.0, 205.0, 205.0]);
+            }
+
+            #[test]
+            fn five_three_one_ends_the_first_weeks_with_an_amrap() {
+                let (targets, _) = five_three_one(&squat(200.0), 0, Some(5.0));
+
+                assert_eq!(
+                    targets
+                        .iter()
+                        .map(|target| (target.weight, target.reps, target.set_type))
+                        .collect::<Vec<_>>(),
+                    vec![
+                        (130.0, 5, SetType::Working),
+                        (150.0, 5, SetType::Working),
+                        (170.0, 5, SetType::Amrap),
+                    ]
+                );
+            }
+
+            #[test]
+            fn five_three_one_deloads_in_the_fourth_week() {
+                let (targets, note) = five_three_one(&squat(200.0), 3, Some(5.0));
+
+                assert!(targets
+                    .iter()
+                    .all(|target| target.set_type == SetType::Working));
+                assert_eq!(
+                    targets
+                        .iter()
+                        .map(|target| target.weight)
+                        .collect::<Vec<_>>(),
+                    vec![80.0, 100.0, 120.0]
+                );
+                assert!(note.contains("deload"));
+            }
+
+            #[test]
+            fn five_three_one_raises_the_training_max_each_cycle() {
+                let history = history(&[(130.0, 5), (140.0, 3), (150.0, 1), (80.0, 5)]);
+                let prescription = prescribe(
+                    Program::FiveThreeOne,
+                    squat(200.0),
+                    &history,
+                    Tz::UTC,
+                    Some(5.0),
+                );
+
+                // 65% of 205
+                assert_eq!(prescription.targets[0].weight, 135.0);
+                assert!(prescription.note.contains("cycle 2 week 1"));
+            }
+
+            #[test]
+            fn five_three_one_rounds_to_what_the_plates_can_load() {
+                let (targets, _) = five_three_one(&squat(205.0), 0, Some(2.5));
+
+                assert_eq!(
+                    targets
+                        .iter()
+                        .map(|target| target.weight)
+                        .collect::<Vec<_>>(),
+                    vec![132.5, 155.0, 175.0]
+                );
+            }
+
+            #[test]
+            fn sessions_group_by_local_day_across_dst() {
+                // clocks in new york jump from 2am to 3am on 2024-03-10, the
+                // first two sets are the same local day but different utc
+                // days, the last two share a utc day but not a local one
+                let tz: Tz = "America/New_York".parse().unwrap();
+                let history = ["2024-03-10T00:30", "2024-03-10T23:30", "2024-03-11T00:30"]
+                    .iter()
+                    .map(|time| set(date::parse_time(time, tz).unwrap(), 135.0, 5))
+                    .collect::<Vec<_>>();
+                let sizes = |tz| {
+                    sessions(&history, tz)
+                        .iter()
+                        .map(|session| session.len())
+                        .collect::<Vec<_>>()
+                };
+
+                assert_eq!(sizes(tz), vec![2, 1]);
+                assert_eq!(sizes(Tz::UTC), vec![1, 2]);
+            }
+        }
+    }
+
+    #[derive(StaticFiles)]
+    struct StaticFile {
+        #[file("/static/htmx.js")]
+        htmx: Js,
+        #[file("/static/tailwind.css")]
+        tailwind: Css,
+        #[file("/static/json-enc.js")]
+        json_enc: Js,
+        #[file("/static/preload.js")]
+        preload: Js,
+        #[file("/static/rest-timer.js")]
+        rest_timer: Js,
+        #[file("/static/time-zone.js")]
+        time_zone: Js,
+    }
+
+    #[derive(Routes, PartialEq, Debug, Clone, Copy)]
+    enum Route {
+        #[get("/")]
+        Root,
+        #[get("/set-form")]
+        SetForm, // logged in
+        #[post("/sets")]
+        CreateSet,
+        #[get("/set-fields")]
+        SetFields,
+        #[get("/exercises")]
+        Exercises,
+        #[post("/exercises")]
+        UpdateExercise,
+        #[get("/measurements")]
+        Measurements,
+        #[post("/measurements")]
+        CreateMeasurement,
+        #[get("/stats")]
+        Stats,
+        #[get("/templates")]
+        Templates,
+        #[post("/templates")]
+        CreateTemplate,
+        #[get("/template")]
+        EditTemplate,
+        #[post("/template")]
+        UpdateTemplate,
+        #[post("/delete-template")]
+        DeleteTemplate,
+        #[post("/template-exercises")]
+        CreateTemplateExercise,
+        #[post("/delete-template-exercise")]
+        DeleteTemplateExercise,
+        #[post("/move-template-exercise")]
+        MoveTemplateExercise,
+        #[get("/program")]
+        Program,
+        #[post("/check-set")]
+        CheckSet,
+        #[get("/sets")]
+        SetList,
+        #[get("/set")]
+        EditSet,
+        #[post("/set")]
+        UpdateSet,
+        #[get("/profile")]
+        Profile,
+        #[post("/time-zone")]
+        UpdateTimeZone,
+        #[get("/plates")]
+        Plates,
+        #[post("/plates")]
+        UpdatePlates,
+        #[get("/plate-hint")]
+        PlateHint,
+        #[post("/delete-set")]
+        DeleteSet,
+        #[post("/undo-delete-set")]
+        UndoDeleteSet,
+        #[get("/dismiss")]
+        Dismiss,
+        #[post("/logout")]
+        Logout,
+        #[get("/export")]
+        Export,
+        #[get("/delete-account")]
+        DeleteAccountForm,
+        #[post("/delete-account")]
+        DeleteAccount,
+        #[get("/login")]
+        LoginForm,
+        #[post("/login")]
+        Login,
+    }
+
+    impl From<Route> for Cow<'static, str> {
+        fn from(value: Route) -> Self {
+            Cow::Owned(value.to_string())
+        }
+    }
+
+    #[allow(unused)]
+    #[JustError]
+    pub enum Error {
+        NotFound,
+        Database(String),
+        InternalServer,
+        RowNotFound,
+        UserNotFound,
+        DatabaseTooNew(i64, i64),
+        Backup(String),
+        /// form input we can't use, handlers re-render the form with it
+        Invalid(String),
+    }
+
+    type Result<T> = std::result::Result<T, Error>;
+
+    mod db {
+        use crate::backend::*;
+        use dubs::{rizz, Connection, JournalMode};
+        use dubs::{Integer, Real, Table, Text};
+
+        #[derive(Clone, Debug)]
+        pub struct Database {
+            pub db: rizz::Database,
+            pub users: Users,
+            pub sessions: Sessions,
+            pub sets: Sets,
+            pub exercises: Exercises,
+            pub measurements: Measurements,
+            pub templates: Templates,
+            pub template_exercises: TemplateExercises,
+            pub schema_migrations: SchemaMigrations,
+        }
+
+        impl Database {
+            fn new(db: rizz::Database) -> Self {
+                let users = Users::new();
+                let sessions = Sessions::new();
+                let sets = Sets::new();
+                let exercises = Exercises::new();
+                let measurements = Measurements::new();
+                let templates = Templates::new();
+                let template_exercises = TemplateExercises::new();
+                let schema_migrations = SchemaMigrations::new();
+
+                Self {
+                    db,
+                    sets,
+                    sessions,
+                    users,
+                    exercises,
+                    measurements,
+                    templates,
+                    template_exercises,
+                    schema_migrations,
+                }
+            }
+
+            pub async fn connect(path: &str) -> Self {
+                let db = Connection::new(path)
+                    .create_if_missing(true)
+                    .journal_mode(JournalMode::Wal)
+                    .foreign_keys(true)
+                    .open()
+                    .await
+                    .expect("Could not connect to database")
+                    .database();
+
+                Database::new(db)
+            }
+
+            /// Opens an existing file as it is, without switching it to wal
+            /// or leaving -wal and -shm files next to it
+            pub async fn read_only(path: &str) -> Result<Self> {
+                // a uri so sqlite takes mode=ro, with the characters it
+                // reads as part of one escaped
+                let uri = format!(
+                    "file:{}?mode=ro",
+                    path.replace('%', "%25")
+                        .replace('?', "%3f")
+                        .replace('#', "%23")
+                );
+                let db = Connection::new(&uri)
+                    .create_if_missing(false)
+                    .open()
+                    .await?
+                    .database();
+
+                Ok(Database::new(db))
+            }
+
+            pub async fn open(path: &str) -> Result<Self> {
+                let db = Database::connect(path).await;
+                let _ = db.migrate().await?;
+
+                Ok(db)
+            }
+
+            /// The newest migration applied to this database, 0 when it has
+            /// never been migrated
+            pub async fn schema_version(&self) -> Result<i64> {
+                let Self {
+                    ref db,
+                    schema_migrations,
+                    ..
+                } = *self;
+                let tables: Vec<Name> = db
+                    .query("select name from sqlite_master where type = 'table' and name = ?")
+                    .bind("schema_migrations")
+                    .all()
+                    .await?;
+                if tables.is_empty() {
+                    return Ok(0);
+                }
+                let rows: Vec<SchemaMigration> = db.select().from(schema_migrations).all().await?;
+
+                Ok(rows.iter().map(|row| row.version).max().unwrap_or(0))
+            }
+
+            pub async fn pending_migrations(&self) -> Result<Vec<&'static Migration>> {
+                let version = self.schema_version().await?;
+                if version > SCHEMA_VERSION {
+                    return Err(Error::DatabaseTooNew(version, SCHEMA_VERSION));
+                }
+
+                Ok(MIGRATIONS
+                    .iter()
+                    .filter(|migration| migration.version > version)
+                    .collect())
+            }
+
+            /// Runs each pending migration in its own transaction and records
+            /// it in `schema_migrations`
+            pub async fn migrate(&self) -> Result<Vec<&'static Migration>> {
+                let Self {
+                    ref db,
+                    schema_migrations,
+                    ..
+                } = *self;
+                let pending = self.pending_migrations().await?;
+                if !pending.is_empty() {
+                    let _ = db.execute(SCHEMA_MIGRATIONS).await?;
+                }
+                for migration in &pending {
+                    let _ = db.execute("begin immediate").await?;
+                    match self.apply(migration).await {
+                        Ok(()) => {
+                            let _ = db.execute("commit").await?;
+                        }
+                        Err(err) => {
+                            let _ = db.execute("rollback").await?;
+                            return Err(err);
+                        }
+                    }
+                }
+
+                Ok(pending)
+            }
+
+            async fn apply(&self, migration: &Migration) -> Result<()> {
+                let Self {
+                    ref db,
+                    schema_migrations,
+                    ..
+                } = *self;
+                for statement in migration.statements {
+                    let _ = db.execute(statement).await?;
+                }
+                let _ = db
+                    .insert(schema_migrations)
+                    .values(SchemaMigration {
+                        version: migration.version,
+                        name: migration.name.to_owned(),
+                        applied_at: now(),
+                    })?
+                    .rows_affected()
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[derive(Deserialize)]
+        pub struct UserSummary {
+            pub id: String,
+            pub created_at: u64,
+            pub sets: u64,
+        }
+
+        #[derive(Deserialize)]
+        pub struct Stats {
+            pub users: u64,
+            pub sessions: u64,
+            pub sets: u64,
+            pub working_sets: u64,
+            /// summed from each user's logged sets, not in sql
+            #[serde(skip)]
+            pub volume: i64,
+        }
+
+        impl Database {
+            pub async fn user_summaries(&self) -> Result<Vec<UserSummary>> {
+                let rows = self
+                    .db
+                    .query(
+                        "select users.id, users.created_at, count(sets.id) as sets
+                        from users
+                        left join sets on sets.user_id = users.id and sets.deleted_at is null
+                        group by users.id
+                        order by users.created_at",
+                    )
+                    .all()
+                    .await?;
+
+                Ok(rows)
+            }
+
+            pub async fn stats(&self) -> Result<Stats> {
+                let Self { ref db, users, .. } = *self;
+                let stats: Stats = db
+                    .query(
+                        "select
+                            (select count(*) from users) as users,
+                            (select count(*) from sessions) as sessions,
+                            (select count(*) from sets where deleted_at is null) as sets,
+                            (select count(*) from sets where deleted_at is null and set_type != 'warm_up') as working_sets",
+                    )
+                    .first()
+                    .await?;
+                let users: Vec<User> = db.select().from(users).all().await?;
+                let mut volume = 0.0;
+                for user in &users {
+                    volume += self
+                        .counted_sets(user, 0)
+                        .await?
+                        .iter()
+                        .map(|set| set.volume())
+                        .sum::<f64>();
+                }
+
+                Ok(Stats {
+                    volume: volume.round() as i64,
+                    ..stats
+                })
+            }
+
+            /// Removes the user in a single statement, the users_before_delete
+            /// trigger clears everything that references them first so it
+            /// all goes or none of it does
+            pub async fn delete_user(&self, id: &str) -> Result<()> {
+                let Self { ref db, users, .. } = *self;
+                let deleted = db
+                    .delete_from(users)
+                    .r#where(eq(users.id, id))
+                    .rows_affected()
+                    .await?;
+
+                match deleted {
+                    0 => Err(Error::UserNotFound),
+                    _ => Ok(()),
+                }
+            }
+
+            pub async fn purge_deleted_sets(&self, before: u64) -> Result<usize> {
+                let Self { ref db, sets, .. } = *self;
+                let purged = db
+                    .delete_from(sets)
+                    .r#where(lt(sets.deleted_at, before))
+                    .rows_affected()
+                    .await?;
+
+                Ok(purged)
+            }
+
+            pub async fn prune_sessions(&self, before: u64) -> Result<usize> {
+                let Self {
+                    ref db, sessions, ..
+                } = *self;
+                let pruned = db
+                    .delete_from(sessions)
+                    .r#where(lt(sessions.created_at, before))
+                    .rows_affected()
+                    .await?;
+
+                Ok(pruned)
+            }
+        }
+
+        pub struct Migration {
+            pub version: i64,
+            pub name: &'static str,
+            pub statements: &'static [&'static str],
+        }
+
+        const SCHEMA_MIGRATIONS: &str = "create table if not exists schema_migrations (
+            version integer primary key,
+            name text not null,
+            applied_at integer not null
+        )";
+
+        /// Append only, never edit a migration that has shipped
+        pub const MIGRATIONS: &[Migration] = &[
+            Migration {
+            version: 1,
+            name: "create users, sessions and sets",
+            statements: &[
+                "create table if not exists users (
+                    id text primary key,
+                    secret text not null,
+                    created_at integer not null
+                )",
+                "create unique index if not exists users_secret_unique_index on users (secret)",
+                "create unique index if not exists users_created_at_unique_index on users (created_at)",
+                "create table if not exists sessions (
+                    id text primary key,
+                    user_id text not null references users(id),
+                    created_at integer not null
+                )",
+                "create table if not exists sets (
+                    id text primary key,
+                    user_id text not null references users(id),
+                    name text not null,
+                    weight integer not null,
+                    reps integer not null,
+                    created_at integer not null
+                )",
+            ],
+        },
+            Migration {
+                version: 2,
+                name: "soft delete sets",
+                statements: &["alter table sets add column deleted_at integer"],
+            },
+            Migration {
+                version: 3,
+                name: "add rpe, rir and notes to sets",
+                statements: &[
+                    "alter table sets add column rpe real",
+                    "alter table sets add column rir integer",
+                    "alter table sets add column notes text",
+                ],
+            },
+            Migration {
+                version: 4,
+                name: "add set types",
+                statements: &[
+                    "alter table sets add column set_type text not null default 'working'",
+                ],
+            },
+            Migration {
+                version: 5,
+                name: "add exercises, set duration and distance",
+                statements: &[
+                    "create table exercises (
+                        id text primary key,
+                        user_id text not null references users(id),
+                        name text not null,
+                        metrics text not null default 'reps_weight',
+                        created_at integer not null
+                    )",
+                    "create unique index exercises_user_id_name_unique_index on exercises (user_id, name)",
+                    "alter table sets add column duration integer",
+                    "alter table sets add column distance integer",
+                ],
+            },
+            Migration {
+                version: 6,
+                name: "add bodyweight exercises and measurements",
+                statements: &[
+                    "alter table exercises add column load text not null default 'external'",
+                    "create table measurements (
+                        id text primary key,
+                        user_id text not null references users(id),
+                        kind text not null,
+                        value real not null,
+                        created_at integer not null
+                    )",
+                    "create index measurements_user_id_kind_created_at_index on measurements (user_id, kind, created_at)",
+                ],
+            },
+            Migration {
+                version: 7,
+                name: "add measured_at to measurements",
+                statements: &[
+                    "alter table measurements add column measured_at integer not null default 0",
+                    "update measurements set measured_at = created_at",
+                    "drop index measurements_user_id_kind_created_at_index",
+                    "create index measurements_user_id_kind_measured_at_index on measurements (user_id, kind, measured_at)",
+                ],
+            },
+            Migration {
+                version: 8,
+                name: "create templates",
+                statements: &[
+                    "create table templates (
+                        id text primary key,
+                        user_id text not null references users(id),
+                        name text not null,
+                        created_at integer not null
+                    )",
+                    "create index templates_user_id_index on templates (user_id)",
+                    "create table template_exercises (
+                        id text primary key,
+                        user_id text not null references users(id),
+                        template_id text not null references templates(id),
+                        position integer not null,
+                        name text not null,
+                        sets integer not null,
+                        reps integer not null,
+                        weight integer not null,
+                        created_at integer not null
+                    )",
+                    "create index template_exercises_template_id_position_index on template_exercises (template_id, position)",
+                ],
+            },
+            Migration {
+                version: 9,
+                name: "add programs to templates",
+                statements: &[
+                    "alter table templates add column program text not null default 'fixed'",
+                    "alter table template_exercises add column increment integer not null default 5",
+                ],
+            },
+            Migration {
+                version: 10,
+                name: "add rest to exercises",
+                statements: &["alter table exercises add column rest integer"],
+            },
+            Migration {
+                version: 11,
+                name: "add muscle groups to exercises",
+                statements: &["alter table exercises add column muscle_group text"],
+            },
+            Migration {
+                version: 12,
+                name: "index sets by user and created at",
+                statements: &[
+                    "create index sets_user_id_created_at_index on sets (user_id, created_at)",
+                ],
+            },
+            Migration {
+                version: 13,
+                name: "add time zones to users",
+                statements: &["alter table users add column time_zone text not null default 'UTC'"],
+            },
+            Migration {
+                version: 14,
+                name: "add performed at to sets",
+                statements: &[
+                    "alter table sets add column performed_at integer not null default 0",
+                    "update sets set performed_at = created_at",
+                    "drop index sets_user_id_created_at_index",
+                    "create index sets_user_id_performed_at_index on sets (user_id, performed_at)",
+                ],
+            },
+            Migration {
+                version: 15,
+                name: "add plate inventories to users",
+                statements: &[
+                    "alter table users add column bar_weight real not null default 45",
+                    "alter table users add column plates text not null default '45, 35, 25, 10, 5, 2.5'",
+                ],
+            },
+            Migration {
+                version: 16,
+                name: "store set weights as real",
+                // sqlite can't change a column's type, so the table is rebuilt
+                statements: &[
+                    "create table sets_new (
+                        id text primary key,
+                        user_id text not null references users(id),
+                        name text not null,
+                        weight real not null,
+                        reps integer not null,
+                        created_at integer not null,
+                        deleted_at integer,
+                        rpe real,
+                        rir integer,
+                        notes text,
+                        set_type text not null default 'working',
+                        duration integer,
+                        distance integer,
+                        performed_at integer not null default 0
+                    )",
+                    "insert into sets_new
+                    select id, user_id, name, weight, reps, created_at, deleted_at, rpe, rir, notes,
+                        set_type, duration, distance, performed_at
+                    from sets",
+                    "drop table sets",
+                    "alter table sets_new rename to sets",
+                    "create index sets_user_id_performed_at_index on sets (user_id, performed_at)",
+                ],
+            },
+            Migration {
+                version: 17,
+                name: "delete user data along with the user",
+                statements: &[
+                    // children first for the foreign keys, a new table with a
+                    // user_id gets its own migration that recreates this
+                    "create trigger users_before_delete before delete on users
+                    begin
+                        delete from template_exercises where user_id = old.id;
+                        delete from templates where user_id = old.id;
+                        delete from measurements where user_id = old.id;
+                        delete from sets where user_id = old.id;
+                        delete from exercises where user_id = old.id;
+                        delete from sessions where user_id = old.id;
+                    end",
+                ],
+            },
+            Migration {
+                version: 18,
+                name: "store template weights as real",
+                // the trigger names template_exercises, sqlite won't rename
+                // the rebuilt table over it so it's dropped and recreated
+                statements: &[
+                    "drop trigger users_before_delete",
+                    "create table template_exercises_new (
+                        id text primary key,
+                        user_id text not null references users(id),
+                        template_id text not null references templates(id),
+                        position integer not null,
+                        name text not null,
+                        sets integer not null,
+                        reps integer not null,
+                        weight real not null,
+                        created_at integer not null,
+                        increment real not null default 5
+                    )",
+                    "insert into template_exercises_new
+                    select id, user_id, template_id, position, name, sets, reps, weight, created_at,
+                        increment
+                    from template_exercises",
+                    "drop table template_exercises",
+                    "alter table template_exercises_new rename to template_exercises",
+                    "create index template_exercises_template_id_position_index on template_exercises (template_id, position)",
+                    "create trigger users_before_delete before delete on users
+                    begin
+                        delete from template_exercises where user_id = old.id;
+                        delete from templates where user_id = old.id;
+                        delete from measurements where user_id = old.id;
+                        delete from sets where user_id = old.id;
+                        delete from exercises where user_id = old.id;
+                        delete from sessions where user_id = old.id;
+                    end",
+                ],
+            },
+        ];
+
+        pub const SCHEMA_VERSION: i64 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "schema_migrations")]
+        pub struct SchemaMigrations {
+            #[rizz(primary_key)]
+            pub version: Integer,
+            #[rizz(not_null)]
+            pub name: Text,
+            #[rizz(not_null)]
+            pub applied_at: Integer,
+        }
+
+        #[derive(Serialize, Deserialize, Debug)]
+        pub struct SchemaMigration {
+            pub version: i64,
+            pub name: String,
+            pub applied_at: u64,
+        }
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "users")]
+        pub struct Users {
+            #[rizz(primary_key)]
+            pub id: Text,
+            #[rizz(not_null)]
+            pub secret: Text,
+            #[rizz(not_null)]
+            pub created_at: Integer,
+            #[rizz(not_null)]
+            pub time_zone: Text,
+            #[rizz(not_null)]
+            pub bar_weight: Real,
+            #[rizz(not_null)]
+            pub plates: Text,
+        }
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "sessions")]
+        pub struct Sessions {
+            #[rizz(primary_key)]
+            pub id: Text,
+            #[rizz(not_null, references = "users(id)")]
+            pub user_id: Text,
+            #[rizz(not_null)]
+            pub created_at: Integer,
+        }
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "sets")]
+        pub struct Sets {
+            #[rizz(primary_key)]
+            pub id: Text,
+            #[rizz(not_null, references = "users(id)")]
+            pub user_id: Text,
+            #[rizz(not_null)]
+            pub name: Text,
+            #[rizz(not_null)]
+            pub weight: Real,
+            #[rizz(not_null)]
+            pub reps: Integer,
+            #[rizz(not_null)]
+            pub created_at: Integer,
+            pub deleted_at: Integer,
+            pub rpe: Real,
+            pub rir: Integer,
+            pub notes: Text,
+            #[rizz(not_null)]
+            pub set_type: Text,
+            pub duration: Integer,
+            pub distance: Integer,
+            #[rizz(not_null)]
+            pub performed_at: Integer,
+        }
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "exercises")]
+        pub struct Exercises {
+            #[rizz(primary_key)]
+            pub id: Text,
+            #[rizz(not_null, references = "users(id)")]
+            pub user_id: Text,
+            #[rizz(not_null)]
+            pub name: Text,
+            #[rizz(not_null)]
+            pub metrics: Text,
+            #[rizz(not_null)]
+            pub created_at: Integer,
+            #[rizz(not_null)]
+            pub load: Text,
+            pub rest: Integer,
+            pub muscle_group: Text,
+        }
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "measurements")]
+        pub struct Measurements {
+            #[rizz(primary_key)]
+            pub id: Text,
+            #[rizz(not_null, references = "users(id)")]
+            pub user_id: Text,
+            #[rizz(not_null)]
+            pub kind: Text,
+            #[rizz(not_null)]
+            pub value: Real,
+            #[rizz(not_null)]
+            pub created_at: Integer,
+            #[rizz(not_null)]
+            pub measured_at: Integer,
+        }
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "templates")]
+        pub struct Templates {
+            #[rizz(primary_key)]
+            pub id: Text,
+            #[rizz(not_null, references = "users(id)")]
+            pub user_id: Text,
+            #[rizz(not_null)]
+            pub name: Text,
+            #[rizz(not_null)]
+            pub created_at: Integer,
+            #[rizz(not_null)]
+            pub program: Text,
+        }
+
+        #[allow(unused)]
+        #[derive(Table, Clone, Copy, Debug)]
+        #[rizz(table = "template_exercises")]
+        pub struct TemplateExercises {
+            #[rizz(primary_key)]
+            pub id: Text,
+            #[rizz(not_null, references = "users(id)")]
+            pub user_id: Text,
+            #[rizz(not_null, references = "templates(id)")]
+            pub template_id: Text,
+            #[rizz(not_null)]
+            pub position: Integer,
+            #[rizz(not_null)]
+            pub name: Text,
+            #[rizz(not_null)]
+            pub sets: Integer,
+            #[rizz(not_null)]
+            pub reps: Integer,
+            #[rizz(not_null)]
+            pub weight: Real,
+            #[rizz(not_null)]
+            pub created_at: Integer,
+            #[rizz(not_null)]
+            pub increment: Real,
+        }
+
+        /// A row that may not be there, every other error still bubbles up
+        fn optional<T>(result: std::result::Result<T, rizz::Error>) -> Result<Option<T>> {
+            match result {
+                Ok(row) => Ok(Some(row)),
+                Err(rizz::Error::RowNotFound) => Ok(None),
+                Err(err) => Err(err.into()),
+            }
+        }
+
+        impl From<rizz::Error> for Error {
+            fn from(value: rizz::Error) -> Self {
+                match value {
+                    rizz::Error::RowNotFound => Error::NotFound,
+                    rizz::Error::Database(err) => Error::Database(err),
+                    _ => Error::InternalServer,
+                }
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Debug, Default)]
+        pub struct User {
+            pub id: String,
+            pub secret: String,
+            pub created_at: u64,
+            /// iana name, set from the browser on sign up
+            pub time_zone: String,
+            pub bar_weight: f64,
+            /// the plate inventory, see `plates::parse`
+            pub plates: String,
+        }
+        impl User {
+            pub fn new() -> Self {
+                Self {
+                    id: ulid(),
+                    secret: ulid(),
+                    created_at: now(),
+                    time_zone: Tz::UTC.name().to_owned(),
+                    bar_weight: DEFAULT_BAR_WEIGHT,
+                    plates: DEFAULT_PLATES.to_owned(),
+                }
+            }
+
+            /// Falls back to utc for zones chrono-tz has since dropped
+            pub fn tz(&self) -> Tz {
+                self.time_zone.parse().unwrap_or(Tz::UTC)
+            }
+
+            pub fn plates(&self) -> Vec<Plate> {
+                plates::parse(&self.plates)
+                    .or_else(|| plates::parse(DEFAULT_PLATES))
+                    .unwrap_or_default()
+            }
+        }
+
+        pub const DEFAULT_BAR_WEIGHT: f64 = 45.0;
+        pub const DEFAULT_PLATES: &str = "45, 35, 25, 10, 5, 2.5";
+
+        #[derive(Serialize)]
+        pub struct UserTimeZone {
+            pub time_zone: String,
+        }
+
+        impl Database {
+            pub async fn update_time_zone(&self, user: &User, time_zone: &str) -> Result<()> {
+                let Self { ref db, users, .. } = *self;
+                let tz: Tz = time_zone.parse().map_err(|_| {
+                    Error::Invalid(format!("{} is not a known time zone", time_zone))
+                })?;
+                let _ = db
+                    .update(users)
+                    .set(UserTimeZone {
+                        time_zone: tz.name().to_owned(),
+                    })?
+                    .r#where(eq(users.id, &user.id))
+                    .rows_affected()
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[derive(Serialize)]
+        pub struct UserPlates {
+            pub bar_weight: f64,
+            pub plates: String,
+        }
+
+        impl Database {
+            pub async fn update_plates(
+                &self,
+                user: &User,
+                bar_weight: f64,
+                plates: &str,
+            ) -> Result<()> {
+                let Self { ref db, users, .. } = *self;
+                let inventory = plates::parse(plates).ok_or_else(|| {
+                    Error::Invalid(format!(
+                        "could not read {:?}, list plate weights with commas",
+                        plates
+                    ))
+                })?;
+                if !bar_weight.is_finite() || bar_weight < 0.0 {
+                    return Err(Error::Invalid("the bar weight has to be 0 or more".into()));
+                }
+                let _ = db
+                    .update(users)
+                    .set(UserPlates {
+                        bar_weight,
+                        plates: plates::format(&inventory),
+                    })?
+                    .r#where(eq(users.id, &user.id))
+                    .rows_affected()
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        /// Everything we store about a user, add new tables here as they
+        /// grow a user_id
+        #[derive(Serialize)]
+        pub struct Export {
+            pub exported_at: u64,
+            pub user: ExportedUser,
+            pub sessions: Vec<ExportedSession>,
+            pub sets: Vec<Set>,
+            pub exercises: Vec<Exercise>,
+            pub measurements: Vec<Measurement>,
+            pub templates: Vec<Template>,
+            pub template_exercises: Vec<TemplateExercise>,
+        }
+
+        /// The user without the secret, which logs in for good
+        #[derive(Serialize)]
+        pub struct ExportedUser {
+            pub id: String,
+            pub created_at: u64,
+            pub time_zone: String,
+            pub bar_weight: f64,
+            pub plates: String,
+        }
+
+        impl From<User> for ExportedUser {
+            fn from(user: User) -> Self {
+                Self {
+                    id: user.id,
+                    created_at: user.created_at,
+                    time_zone: user.time_zone,
+                    bar_weight: user.bar_weight,
+                    plates: user.plates,
+                }
+            }
+        }
+
+        /// Just when it started, the id is a live cookie
+        #[derive(Serialize)]
+        pub struct ExportedSession {
+            pub created_at: u64,
+        }
+
+        impl Database {
+            pub async fn export(&self, user: User) -> Result<Export> {
+                let Self {
+                    ref db,
+                    sessions,
+                    sets,
+                    exercises,
+                    measurements,
+                    templates,
+                    template_exercises,
+                    ..
+                } = *self;
+                let sessions: Vec<Session> = db
+                    .select()
+                    .from(sessions)
+                    .r#where(eq(sessions.user_id, &user.id))
+                    .order(vec![asc(sessions.created_at)])
+                    .all()
+                    .await?;
+                let sets: Vec<Set> = db
+                    .select()
+                    .from(sets)
+                    .r#where(eq(sets.user_id, &user.id))
+                    .order(vec![asc(sets.created_at)])
+                    .all()
+                    .await?;
+                let exercises: Vec<Exercise> = db
+                    .select()
+                    .from(exercises)
+                    .r#where(eq(exercises.user_id, &user.id))
+                    .order(vec![asc(exercises.name)])
+                    .all()
+                    .await?;
+                let measurements: Vec<Measurement> = db
+                    .select()
+                    .from(measurements)
+                    .r#where(eq(measurements.user_id, &user.id))
+                    .order(vec![asc(measurements.measured_at)])
+                    .all()
+                    .await?;
+                let templates: Vec<Template> = db
+                    .select()
+                    .from(templates)
+                    .r#where(eq(templates.user_id, &user.id))
+                    .order(vec![asc(templates.created_at)])
+                    .all()
+                    .await?;
+                let template_exercises: Vec<TemplateExercise> = db
+                    .select()
+                    .from(template_exercises)
+                    .r#where(eq(template_exercises.user_id, &user.id))
+                    .order(vec![
+                        asc(template_exercises.template_id),
+                        asc(template_exercises.position),
+                    ])
+                    .all()
+                    .await?;
+
+                Ok(Export {
+                    exported_at: now(),
+                    user: user.into(),
+                    sessions: sessions
+                        .into_iter()
+                        .map(|session| ExportedSession {
+                            created_at: session.created_at,
+                        })
+                        .collect(),
+                    sets,
+                    exercises,
+                    measurements,
+                    templates,
+                    template_exercises,
+                })
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Debug)]
+        pub struct Session {
+            pub id: String,
+            pub user_id: String,
+            pub created_at: u64,
+        }
+
+        #[derive(Clone, Serialize, Deserialize, Debug)]
+        pub struct Set {
+            pub id: String,
+            pub user_id: String,
+            pub name: String,
+            pub weight: f64,
+            pub reps: usize,
+            pub created_at: u64,
+            pub deleted_at: Option<u64>,
+            pub rpe: Option<f64>,
+            pub rir: Option<usize>,
+            pub notes: Option<String>,
+            pub set_type: SetType,
+            pub duration: Option<usize>,
+            pub distance: Option<usize>,
+            /// when the set was lifted, `created_at` is when it was logged
+            pub performed_at: u64,
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+        pub struct Exercise {
+            pub id: String,
+            pub user_id: String,
+            pub name: String,
+            pub metrics: Metrics,
+            pub created_at: u64,
+            pub load: Load,
+            pub rest: Option<usize>,
+            pub muscle_group: Option<MuscleGroup>,
+        }
+
+        impl Exercise {
+            pub fn new(user: &User, name: &str) -> Self {
+                Self {
+                    id: ulid(),
+                    user_id: user.id.clone(),
+                    name: name.to_owned(),
+                    created_at: now(),
+                    ..Default::default()
+                }
+            }
+        }
+
+        /// Whether the weight on a set is all there is or comes on top of
+        /// the lifter's bodyweight
+        #[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Load {
+            #[default]
+            External,
+            Bodyweight,
+        }
+
+        impl Load {
+            pub const ALL: [Load; 2] = [Load::External, Load::Bodyweight];
+
+            pub fn value(&self) -> &'static str {
+                match self {
+                    Load::External => "external",
+                    Load::Bodyweight => "bodyweight",
+                }
+            }
+
+            pub fn label(&self) -> &'static str {
+                match self {
+                    Load::External => "weighted",
+                    Load::Bodyweight => "bodyweight",
+                }
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum MuscleGroup {
+            Chest,
+            Back,
+            Shoulders,
+            Arms,
+            Legs,
+            Core,
+            FullBody,
+        }
+
+        impl MuscleGroup {
+            pub const ALL: [MuscleGroup; 7] = [
+                MuscleGroup::Chest,
+                MuscleGroup::Back,
+                MuscleGroup::Shoulders,
+                MuscleGroup::Arms,
+                MuscleGroup::Legs,
+                MuscleGroup::Core,
+                MuscleGroup::FullBody,
+            ];
+
+            pub fn value(&self) -> &'static str {
+                match self {
+                    MuscleGroup::Chest => "chest",
+                    MuscleGroup::Back => "back",
+                    MuscleGroup::Shoulders => "shoulders",
+                    MuscleGroup::Arms => "arms",
+                    MuscleGroup::Legs => "legs",
+                    MuscleGroup::Core => "core",
+                    MuscleGroup::FullBody => "full_body",
+                }
+            }
+
+            pub fn label(&self) -> &'static str {
+                match self {
+                    MuscleGroup::FullBody => "full body",
+                    muscle_group => muscle_group.value(),
+                }
+            }
+        }
+
+        impl std::str::FromStr for SetType {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self> {
+                SetType::ALL
+                    .into_iter()
+                    .find(|set_type| set_type.value() == s)
+                    .ok_or(Error::NotFound)
+            }
+        }
+
+        impl std::str::FromStr for MuscleGroup {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self> {
+                MuscleGroup::ALL
+                    .into_iter()
+                    .find(|muscle_group| muscle_group.value() == s)
+                    .ok_or(Error::NotFound)
+            }
+        }
+
+        /// Sets are summed into buckets this wide in sql and regrouped into
+        /// local days afterwards, every utc offset in use is a multiple of it
+        const BUCKET: u64 = 900;
+
+        #[derive(Deserialize)]
+        struct Bucket {
+            bucket: u64,
+            sets: i64,
+        }
+
+        impl Bucket {
+            fn day(&self, tz: Tz) -> i64 {
+                date::day(self.bucket * BUCKET, tz)
+            }
+        }
+
+        #[derive(Clone, Default)]
+        pub struct Week {
+            /// local day of the week's monday
+            pub monday: i64,
+            pub tonnage: i64,
+            pub sets: i64,
+            pub days: i64,
+        }
+
+        #[derive(Deserialize)]
+        pub struct ExerciseCount {
+            pub name: String,
+            pub sets: i64,
+        }
+
+        #[derive(Deserialize)]
+        pub struct MuscleGroupCount {
+            pub muscle_group: Option<MuscleGroup>,
+            pub sets: i64,
+        }
+
+        /// Warm-ups left out, same as everywhere else volume is counted
+        pub struct TrainingStats {
+            /// oldest first with a row for every week, trained or not
+            pub weeks: Vec<Week>,
+            pub muscle_groups: Vec<MuscleGroupCount>,
+            pub exercises: Vec<ExerciseCount>,
+            /// weeks in a row with at least one set, this week only counts
+            /// once it has one
+            pub streak: usize,
+            pub longest_streak: usize,
+        }
+
+        impl Database {
+            /// Sets since `since` counted per bucket, oldest first. Passing a
+            /// set type leaves it out
+            async fn buckets(
+                &self,
+                user: &User,
+                since: u64,
+                without: Option<SetType>,
+            ) -> Result<Vec<Bucket>> {
+                let rows = self
+                    .db
+                    .query(
+                        "select performed_at / ? as bucket,
+                            count(*) as sets
+                        from sets
+                        where user_id = ? and deleted_at is null and set_type != ? and performed_at >= ?
+                        group by bucket
+                        order by bucket",
+                    )
+                    .bind(BUCKET)
+                    .bind(&user.id)
+                    // no set type is stored as an empty string
+                    .bind(without.map(|set_type| set_type.value()).unwrap_or_default())
+                    .bind(since)
+                    .all()
+                    .await?;
+
+                Ok(rows)
+            }
+
+            pub async fn training_stats(&self, user: &User, weeks: i64) -> Result<TrainingStats> {
+                let tz = user.tz();
+                let this_monday = date::monday(date::today(tz));
+                let first_monday = this_monday - (weeks - 1) * 7;
+                let since = date::start_of(first_monday, tz);
+                let mut weeks = (0..weeks)
+                    .map(|week| Week {
+                        monday: first_monday + week * 7,
+                        ..Default::default()
+                    })
+                    .collect::<Vec<_>>();
+                let mut last_day = None;
+                for bucket in self.buckets(user, since, Some(SetType::WarmUp)).await? {
+                    let day = bucket.day(tz);
+                    let index = (date::monday(day) - first_monday) / 7;
+                    let Some(week) = usize::try_from(index).ok().and_then(|i| weeks.get_mut(i))
+                    else {
+                        continue;
+                    };
+                    week.sets += bucket.sets;
+                    if last_day != Some(day) {
+                        week.days += 1;
+                        last_day = Some(day);
+                    }
+                }
+                let mut volumes = vec![0.0; weeks.len()];
+                for set in self.counted_sets(user, since).await? {
+                    let day = date::day(set.set.performed_at, tz);
+                    let index = (date::monday(day) - first_monday) / 7;
+                    if let Some(volume) =
+                        usize::try_from(index).ok().and_then(|i| volumes.get_mut(i))
+                    {
+                        *volume += set.volume();
+                    }
+                }
+                for (week, volume) in weeks.iter_mut().zip(volumes) {
+                    week.tonnage = volume.round() as i64;
+                }
+                let muscle_groups: Vec<MuscleGroupCount> = self
+                    .db
+                    .query(
+                        "select exercises.muscle_group, count(*) as sets
+                        from sets
+                        left join exercises on exercises.user_id = sets.user_id and exercises.name = sets.name
+                        where sets.user_id = ? and sets.deleted_at is null and sets.set_type != 'warm_up' and sets.performed_at >= ?
+                        group by exercises.muscle_group
+                        order by sets desc",
+                    )
+                    .bind(&user.id)
+                    .bind(since)
+                    .all()
+                    .await?;
+                let exercises: Vec<ExerciseCount> = self
+                    .db
+                    .query(
+                        "select name, count(*) as sets
+                        from sets
+                        where user_id = ? and deleted_at is null and set_type != 'warm_up' and performed_at >= ?
+                        group by name
+                        order by sets desc, name",
+                    )
+                    .bind(&user.id)
+                    .bind(since)
+                    .all()
+                    .await?;
+                let mut trained = self
+                    .buckets(user, 0, Some(SetType::WarmUp))
+                    .await?
+                    .iter()
+                    .map(|bucket| date::monday(bucket.day(tz)).div_euclid(7))
+                    .collect::<Vec<_>>();
+                trained.dedup();
+                let (streak, longest_streak) = streaks(&trained, this_monday.div_euclid(7));
+
+                Ok(TrainingStats {
+                    weeks,
+                    muscle_groups,
+                    exercises,
+                    streak,
+                    longest_streak,
+                })
+            }
+        }
+
+        pub struct TrainingDay {
+            /// local days since the unix epoch
+            pub day: i64,
+            pub sets: i64,
+        }
+
+        impl Database {
+            /// Local days with at least one set since `since`, warm-ups
+            /// included since they still mean the user showed up
+            pub async fn training_days(&self, user: &User, since: i64) -> Result<Vec<TrainingDay>> {
+                let tz = user.tz();
+                let mut days: Vec<TrainingDay> = vec![];
+                for bucket in self.buckets(user, date::start_of(since, tz), None).await? {
+                    let day = bucket.day(tz);
+                    match days.last_mut() {
+                        Some(last) if last.day == day => last.sets += bucket.sets,
+                        _ => days.push(TrainingDay {
+                            day,
+                            sets: bucket.sets,
+                        }),
+                    }
+                }
+
+                Ok(days)
+            }
+        }
+
+        /// The current and longest runs of consecutive weeks, `weeks` is
+        /// sorted and distinct
+        fn streaks(weeks: &[i64], this_week: i64) -> (usize, usize) {
+            let mut longest = 0;
+            let mut run = 0;
+            let mut previous: Option<i64> = None;
+            for &week in weeks {
+                run = match previous {
+                    Some(previous) if previous + 1 == week => run + 1,
+                    _ => 1,
+                };
+                longest = longest.max(run);
+                previous = Some(week);
+            }
+            // a run is still going if it reached this week or last week
+            let current = match previous {
+                Some(last) if last + 1 >= this_week => run,
+                _ => 0,
+            };
+
+            (current, longest)
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::streaks;
+
+            #[test]
+            fn streaks_without_training_are_zero() {
+                assert_eq!(streaks(&[], 10), (0, 0));
+            }
+
+            #[test]
+            fn streaks_keep_going_until_a_week_is_missed() {
+                assert_eq!(streaks(&[8, 9, 10], 10), (3, 3));
+                // this week doesn't break the run until it's over
+                assert_eq!(streaks(&[7, 8, 9], 10), (3, 3));
+                assert_eq!(streaks(&[7, 8, 9], 11), (0, 3));
+            }
+
+            #[test]
+            fn streaks_track_the_longest_run_separately() {
+                assert_eq!(streaks(&[1, 2, 3, 4, 8, 9], 9), (2, 4));
+            }
+        }
+
+        /// A set along with what it takes to work out the load lifted
+        pub struct LoggedSet {
+            pub set: Set,
+            pub load: Load,
+            pub bodyweight: Option<f64>,
+        }
+
+        impl LoggedSet {
+            /// Bodyweight exercises add the latest weigh-in before the set,
+            /// so an assisted pull-up at -40 is bodyweight - 40
+            pub fn effective_load(&self) -> Option<f64> {
+                let weight = self.set.weight;
+                match self.load {
+                    Load::External => Some(weight),
+                    Load::Bodyweight => self.bodyweight.map(|bodyweight| bodyweight + weight),
+                }
+            }
+
+            /// Load times reps, every volume total goes through here. warm-ups
+            /// and bodyweight sets without a weigh-in add nothing
+            pub fn volume(&self) -> f64 {
+                if !self.set.set_type.counts() {
+                    return 0.0;
+                }
+                let load = self.effective_load().unwrap_or_default().max(0.0);
+
+                load * self.set.reps as f64
+            }
+
+            /// Epley, past 12 reps it stops meaning much
+            pub fn e1rm(&self) -> Option<f64> {
+                let reps = self.set.reps;
+                if !self.set.set_type.counts() || !(2..=12).contains(&reps) {
+                    return None;
+                }
+                let load = self.effective_load().filter(|load| *load > 0.0)?;
+
+                Some(load * (1.0 + reps as f64 / 30.0))
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Debug)]
+        pub struct Measurement {
+            pub id: String,
+            pub user_id: String,
+            pub kind: MeasurementKind,
+            pub value: f64,
+            pub created_at: u64,
+            pub measured_at: u64,
+        }
+
+        impl Measurement {
+            pub fn new(user: &User, kind: MeasurementKind, value: f64, measured_at: u64) -> Self {
+                Self {
+                    id: ulid(),
+                    user_id: user.id.clone(),
+                    kind,
+                    value,
+                    created_at: now(),
+                    measured_at,
+                }
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum MeasurementKind {
+            #[default]
+            Bodyweight,
+            BodyFat,
+            Waist,
+            Chest,
+            Hips,
+            Arms,
+            Thighs,
+        }
+
+        impl MeasurementKind {
+            pub const ALL: [MeasurementKind; 7] = [
+                MeasurementKind::Bodyweight,
+                MeasurementKind::BodyFat,
+                MeasurementKind::Waist,
+                MeasurementKind::Chest,
+                MeasurementKind::Hips,
+                MeasurementKind::Arms,
+                MeasurementKind::Thighs,
+            ];
+
+            pub fn value(&self) -> &'static str {
+                match self {
+                    MeasurementKind::Bodyweight => "bodyweight",
+                    MeasurementKind::BodyFat => "body_fat",
+                    MeasurementKind::Waist => "waist",
+                    MeasurementKind::Chest => "chest",
+                    MeasurementKind::Hips => "hips",
+                    MeasurementKind::Arms => "arms",
+                    MeasurementKind::Thighs => "thighs",
+                }
+            }
+
+            pub fn label(&self) -> &'static str {
+                match self {
+                    MeasurementKind::BodyFat => "body fat",
+                    kind => kind.value(),
+                }
+            }
+
+            pub fn unit(&self) -> &'static str {
+                match self {
+                    MeasurementKind::Bodyweight => "lbs",
+                    MeasurementKind::BodyFat => "%",
+                    _ => "in",
+                }
+            }
+        }
+
+        /// The rest since the last set and how long the exercise wants
+        pub struct Rest {
+            pub name: String,
+            pub since: u64,
+            pub target: Option<usize>,
+        }
+
+        /// Past this the last set was a different workout
+        const REST_LIMIT: u64 = 60 * 60;
+
+        /// Weigh-ins, oldest first
+        pub struct Bodyweights(Vec<Measurement>);
+
+        impl Bodyweights {
+            /// The last weigh-in at or before `time`, or the first one ever
+            /// for sets logged before the user started weighing in
+            pub fn at(&self, time: u64) -> Option<f64> {
+                self.0
+                    .iter()
+                    .rev()
+                    .find(|measurement| measurement.measured_at <= time)
+                    .or(self.0.first())
+                    .map(|measurement| measurement.value)
+            }
+
+            pub fn latest(&self) -> Option<f64> {
+                self.0.last().map(|measurement| measurement.value)
+            }
+        }
+
+        impl Database {
+            /// Oldest first, which is the order charts and lookups want
+            pub async fn measurements(
+                &self,
+                user: &User,
+                kind: MeasurementKind,
+            ) -> Result<Vec<Measurement>> {
+                let Self {
+                    ref db,
+                    measurements,
+                    ..
+                } = *self;
+                let rows: Vec<Measurement> = db
+                    .select()
+                    .from(measurements)
+                    .r#where(and(
+                        eq(measurements.user_id, &user.id),
+                        eq(measurements.kind, kind.value()),
+                    ))
+                    .order(vec![asc(measurements.measured_at)])
+                    .all()
+                    .await?;
+
+                Ok(rows)
+            }
+
+            pub async fn bodyweights(&self, user: &User) -> Result<Bodyweights> {
+                let rows = self.measurements(user, MeasurementKind::Bodyweight).await?;
+
+                Ok(Bodyweights(rows))
+            }
+
+            pub async fn logged_sets(&self, user: &User, sets: Vec<Set>) -> Result<Vec<LoggedSet>> {
+                let Self {
+                    ref db, exercises, ..
+                } = *self;
+                let bodyweight_exercises: Vec<Exercise> = db
+                    .select()
+                    .from(exercises)
+                    .r#where(and(
+                        eq(exercises.user_id, &user.id),
+                        eq(exercises.load, Load::Bodyweight.value()),
+                    ))
+                    .all()
+                    .await?;
+                let bodyweights = self.bodyweights(user).await?;
+
+                Ok(sets
+                    .into_iter()
+                    .map(|set| {
+                        let load = match bodyweight_exercises
+                            .iter()
+                            .any(|exercise| exercise.name == set.name)
+                        {
+                            true => Load::Bodyweight,
+                            false => Load::External,
+                        };
+                        LoggedSet {
+                            bodyweight: bodyweights.at(set.performed_at),
+                            load,
+                            set,
+                        }
+                    })
+                    .collect())
+            }
+
+            /// Sets since `since` that count toward volume
+            async fn counted_sets(&self, user: &User, since: u64) -> Result<Vec<LoggedSet>> {
+                let Self { ref db, sets, .. } = *self;
+                let rows: Vec<Set> = db
+                    .select()
+                    .from(sets)
+                    .r#where(and(
+                        and(eq(sets.user_id, &user.id), is_null(sets.deleted_at)),
+                        and(
+                            ne(sets.set_type, SetType::WarmUp.value()),
+                            gte(sets.performed_at, since),
+                        ),
+                    ))
+                    .order(vec![asc(sets.performed_at)])
+                    .all()
+                    .await?;
+
+                self.logged_sets(user, rows).await
+            }
+
+            /// The 30 latest sets that haven't been deleted
+            pub async fn recent_sets(&self, user: &User) -> Result<Vec<LoggedSet>> {
+                let Self { ref db, sets, .. } = *self;
+                let rows: Vec<Set> = db
+                    .select()
+                    .from(sets)
+                    .r#where(and(eq(sets.user_id, &user.id), is_null(sets.deleted_at)))
+                    .order(vec![desc(sets.performed_at)])
+                    .limit(30)
+                    .all()
+                    .await?;
+
+                self.logged_sets(user, rows).await
+            }
+
+            /// Sets matching every filter that's set, newest first. Dates and
+            /// weights that don't parse are ignored rather than matching nothing
+            pub async fn search_sets(
+                &self,
+                user: &User,
+                filter: &SetFilter,
+            ) -> Result<Vec<LoggedSet>> {
+                let Self { ref db, sets, .. } = *self;
+                let tz = user.tz();
+                let mut clause = and(eq(sets.user_id, &user.id), is_null(sets.deleted_at));
+                if let Some(name) = &filter.name {
+                    clause = and(clause, contains(sets.name, name));
+                }
+                if let Some(from) = filter.from.as_deref().and_then(date::parse) {
+                    clause = and(clause, gte(sets.performed_at, date::start_of(from, tz)));
+                }
+                if let Some(to) = filter.to.as_deref().and_then(date::parse) {
+                    clause = and(clause, lt(sets.performed_at, date::start_of(to + 1, tz)));
+                }
+                let min_weight = filter
+                    .min_weight
+                    .as_deref()
+                    .and_then(|weight| weight.parse::<f64>().ok())
+                    .filter(|weight| weight.is_finite());
+                if let Some(min_weight) = min_weight {
+                    clause = and(clause, gte(sets.weight, min_weight));
+                }
+                if let Some(set_type) = filter.set_type {
+                    clause = and(clause, eq(sets.set_type, set_type.value()));
+                }
+                let rows: Vec<Set> = db
+                    .select()
+                    .from(sets)
+                    .r#where(clause)
+                    .order(vec![desc(sets.performed_at)])
+                    .limit(SEARCH_LIMIT)
+                    .all()
+                    .await?;
+
+                self.logged_sets(user, rows).await
+            }
+
+            pub async fn rest(&self, user: &User) -> Result<Option<Rest>> {
+                let set = match self.last_set(user, None).await? {
+                    Some(set) if now().saturating_sub(set.performed_at) < REST_LIMIT => set,
+                    _ => return Ok(None),
+                };
+                let exercise = self.exercise(user, &set.name).await?;
+
+                Ok(Some(Rest {
+                    name: set.name,
+                    since: set.performed_at,
+                    target: exercise.rest,
+                }))
+            }
+
+            /// The latest set, of one exercise when there's a name
+            pub async fn last_set(&self, user: &User, name: Option<&str>) -> Result<Option<Set>> {
+                let Self { ref db, sets, .. } = *self;
+                let mut clause = and(eq(sets.user_id, &user.id), is_null(sets.deleted_at));
+                if let Some(name) = name {
+                    clause = and(clause, eq(sets.name, name));
+                }
+
+                optional(
+                    db.select()
+                        .from(sets)
+                        .r#where(clause)
+                        .order(vec![desc(sets.performed_at)])
+                        .first()
+                        .await,
+                )
+            }
+
+            pub async fn set(&self, user: &User, id: &str) -> Result<Set> {
+                let Self { ref db, sets, .. } = *self;
+                let set: Set = db
+                    .select()
+                    .from(sets)
+                    .r#where(and(
+                        and(eq(sets.id, id), eq(sets.user_id, &user.id)),
+                        is_null(sets.deleted_at),
+                    ))
+                    .first()
+                    .await?;
+
+                Ok(set)
+            }
+
+            pub async fn save_set(&self, set: Set) -> Result<()> {
+                let Self { ref db, sets, .. } = *self;
+                let _ = db
+                    .update(sets)
+                    .set(&set)?
+                    .r#where(and(eq(sets.id, &set.id), eq(sets.user_id, &set.user_id)))
+                    .rows_affected()
+                    .await?;
+
+                Ok(())
+            }
+
+            /// A soft deleted set that can still be undone
+            pub async fn deleted_set(&self, user: &User, id: &str) -> Result<Option<Set>> {
+                let Self { ref db, sets, .. } = *self;
+                optional(
+                    db.select()
+                        .from(sets)
+                        .r#where(and(
+                            and(eq(sets.id, id), eq(sets.user_id, &user.id)),
+                            is_not_null(sets.deleted_at),
+                        ))
+                        .first()
+                        .await,
+                )
+            }
+        }
+
+        /// What an exercise is measured in, sets keep every column and the
+        /// set form only shows these
+        #[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Metrics {
+            #[default]
+            RepsWeight,
+            Duration,
+            DurationDistance,
+            WeightDistance,
+        }
+
+        impl Metrics {
+            pub const ALL: [Metrics; 4] = [
+                Metrics::RepsWeight,
+                Metrics::Duration,
+                Metrics::DurationDistance,
+                Metrics::WeightDistance,
+            ];
+
+            pub fn value(&self) -> &'static str {
+                match self {
+                    Metrics::RepsWeight => "reps_weight",
+                    Metrics::Duration => "duration",
+                    Metrics::DurationDistance => "duration_distance",
+                    Metrics::WeightDistance => "weight_distance",
+                }
+            }
+
+            pub fn label(&self) -> &'static str {
+                match self {
+                    Metrics::RepsWeight => "reps and weight",
+                    Metrics::Duration => "time",
+                    Metrics::DurationDistance => "time and distance",
+                    Metrics::WeightDistance => "weight and distance",
+                }
+            }
+
+            pub fn reps(&self) -> bool {
+                matches!(self, Metrics::RepsWeight)
+            }
+
+            pub fn weight(&self) -> bool {
+                matches!(self, Metrics::RepsWeight | Metrics::WeightDistance)
+            }
+
+            pub fn duration(&self) -> bool {
+                matches!(self, Metrics::Duration | Metrics::DurationDistance)
+            }
 
-        pub fn button() -> Tag {
-            html::button.class("flex rounded-md bg-orange-500 active:bg-orange-700 text-white p-4 items-center justify-center uppercase w-full")
+            pub fn distance(&self) -> bool {
+                matches!(self, Metrics::DurationDistance | Metrics::WeightDistance)
+            }
         }
 
-        fn head() -> impl Render {
-            let static_files = StaticFile::once();
-            html::head((
-                link.href(static_files.tailwind.clone()).rel("stylesheet"),
-                script.src(static_files.htmx.clone()).defer(),
-                script.src(static_files.json_enc.clone()).defer(),
-                script.src(static_files.preload.clone()).defer(),
-                meta.charset("UTF-8"),
-                meta.content("text/html; charset=utf-8")
-                    .attr("http-equiv", "Content-Type"),
-                meta.name("viewport")
-                    .content("width=device-width, initial-scale=1, user-scalable=no"),
-            ))
+        #[derive(Deserialize)]
+        struct Name {
+            name: String,
         }
 
-        fn plus_circle_icon() -> impl Render {
-            raw(r#"
-            <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
-              <path stroke-linecap="round" stroke-linejoin="round" d="M12 9v6m3-3H9m12 0a9 9 0 1 1-18 0 9 9 0 0 1 18 0Z" />
-            </svg>
-        "#)
-        }
+        impl Database {
+            /// The saved exercise or the defaults for one that has only ever
+            /// been typed into the set form
+            pub async fn exercise(&self, user: &User, name: &str) -> Result<Exercise> {
+                let Self {
+                    ref db, exercises, ..
+                } = *self;
+                let exercise: Option<Exercise> = optional(
+                    db.select()
+                        .from(exercises)
+                        .r#where(and(
+                            eq(exercises.user_id, &user.id),
+                            eq(exercises.name, name),
+                        ))
+                        .first()
+                        .await,
+                )?;
 
-        fn list_icon() -> impl Render {
-            raw(
-                r#"<svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
-  <path stroke-linecap="round" stroke-linejoin="round" d="M8.25 6.75h12M8.25 12h12m-12 5.25h12M3.75 6.75h.007v.008H3.75V6.75Zm.375 0a.375.375 0 1 1-.75 0 .375.375 0 0 1 .75 0ZM3.75 12h.007v.008H3.75V12Zm.375 0a.375.375 0 1 1-.75 0 .375.375 0 0 1 .75 0Zm-.375 5.25h.007v.008H3.75v-.008Zm.375 0a.375.375 0 1 1-.75 0 .375.375 0 0 1 .75 0Z" />
-</svg>
-"#,
-            )
-        }
+                Ok(exercise.unwrap_or_else(|| Exercise::new(user, name)))
+            }
 
-        fn user_circle_icon() -> impl Render {
-            raw(
-                r#"<svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" data-slot="icon" class="w-6 h-6">
-  <path stroke-linecap="round" stroke-linejoin="round" d="M17.982 18.725A7.488 7.488 0 0 0 12 15.75a7.488 7.488 0 0 0-5.982 2.975m11.963 0a9 9 0 1 0-11.963 0m11.963 0A8.966 8.966 0 0 1 12 21a8.966 8.966 0 0 1-5.982-2.275M15 9.75a3 3 0 1 1-6 0 3 3 0 0 1 6 0Z" />
-</svg>
-"#,
-            )
-        }
+            /// Saved exercises plus every other name in the user's sets
+            pub async fn exercises(&self, user: &User) -> Result<Vec<Exercise>> {
+                let Self {
+                    ref db, exercises, ..
+                } = *self;
+                let mut saved: Vec<Exercise> = db
+                    .select()
+                    .from(exercises)
+                    .r#where(eq(exercises.user_id, &user.id))
+                    .all()
+                    .await?;
+                let names: Vec<Name> = db
+                    .query(
+                        "select distinct name from sets
+                        where user_id = ? and deleted_at is null",
+                    )
+                    .bind(&user.id)
+                    .all()
+                    .await?;
+                for Name { name } in names {
+                    if !saved.iter().any(|exercise| exercise.name == name) {
+                        saved.push(Exercise::new(user, &name));
+                    }
+                }
+                saved.sort_by(|a, b| a.name.cmp(&b.name));
 
-        fn nav_link(
-            route: Route,
-            current_route: Route,
-            icon: impl Render,
-            s: &'static str,
-        ) -> impl Render {
-            let mut class = "flex flex-col text-center justify-center items-center".to_owned();
-            if route == current_route {
-                class.push_str(" text-orange-500");
+                Ok(saved)
             }
-            a.class(class).attr1("preload").href(route)((
-                span.class("visible lg:invisible")(icon),
-                span(s),
-            ))
-        }
 
-        fn nav(route: Route) -> impl Render {
-            html::nav.class(
-            "text-center lg:max-w-md w-full dark:bg-gray-800 bg-gray-300 lg:bg-transparent lg:dark:bg-transparent flex lg:mx-auto justify-around items-center lg:py-6 py-2 absolute bottom-0 lg:bottom-auto lg:relative",
-        )((
-            nav_link(Route::SetList, route, list_icon(), "Sets"),
-            nav_link(Route::SetForm, route, plus_circle_icon(), "Add a set"),
-            nav_link(Route::Profile, route, user_circle_icon(), "Profile"),
-        ))
-        }
+            pub async fn save_exercise(&self, exercise: Exercise) -> Result<()> {
+                let Self {
+                    ref db, exercises, ..
+                } = *self;
+                let updated = db
+                    .update(exercises)
+                    .set(&exercise)?
+                    .r#where(eq(exercises.id, &exercise.id))
+                    .rows_affected()
+                    .await?;
+                if updated == 0 {
+                    let _ = db
+                        .insert(exercises)
+                        .values(exercise)?
+                        .rows_affected()
+                        .await?;
+                }
 
-        fn body(route: Route, inner: impl Render) -> impl Render {
-            html::body
-                .class("h-screen dark:bg-gray-900 dark:text-white")
-                .attr("hx-boost", "true")
-                .attr("hx-push-url", "true")
-                .attr("hx-ext", "json-enc, preload")((
-                nav(route),
-                html::main.class("max-w-lg mx-auto lg:mt-16 pt-4")(inner),
-            ))
+                Ok(())
+            }
         }
 
-        pub fn render(route: Route, inner: impl Render) -> Result<Responder> {
-            Ok(response(route, inner))
+        #[derive(Serialize, Deserialize, Clone, Debug)]
+        pub struct Template {
+            pub id: String,
+            pub user_id: String,
+            pub name: String,
+            pub created_at: u64,
+            pub program: Program,
         }
 
-        pub fn response(route: Route, inner: impl Render) -> Responder {
-            res().render((doctype("html"), html((head(), body(route, inner)))))
+        impl Template {
+            pub fn new(user: &User, name: &str) -> Self {
+                Self {
+                    id: ulid(),
+                    user_id: user.id.clone(),
+                    name: name.to_owned(),
+                    created_at: now(),
+                    program: Program::default(),
+                }
+            }
         }
 
-        fn label(name: &'static str) -> impl Render {
-            html::label.class("flex flex-col gap-1").r#for(name)(name)
+        #[derive(Serialize)]
+        pub struct TemplateSettings {
+            pub name: String,
+            pub program: Program,
         }
 
-        fn text_input() -> Tag {
-            input.class("block w-full rounded-md border-0 px-2 py-4 dark:bg-gray-700 dark:text-white light:text-gray-900 outline-0 focus:outline-0 focus:ring-0 focus-visible:outline-0 focus:outline-none placeholder:text-gray-400").r#type("text")
+        /// How a template's targets change from one session to the next,
+        /// see the `program` module
+        #[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum Program {
+            #[default]
+            Fixed,
+            Linear,
+            FiveThreeOne,
         }
 
-        fn number_input(name: &'static str, value: usize) -> impl Render {
-            input.class("block w-full rounded-md border-0 px-2 py-4 dark:bg-gray-700 dark:text-white light:text-gray-900 outline-0 focus:outline-0 focus:ring-0 focus-visible:outline-0 focus:outline-none placeholder:text-gray-400").r#type("number").name(name).value(value.to_string())
-        }
+        impl Program {
+            pub const ALL: [Program; 3] = [Program::Fixed, Program::Linear, Program::FiveThreeOne];
 
-        fn suggest_input(
-            name: &'static str,
-            options: Vec<String>,
-            value: String,
-            autofocus: bool,
-        ) -> impl Render {
-            let list = format!("{}_list", name);
-            let mut text_input = text_input()
-                .name(name)
-                .value(value)
-                .id(name)
-                .attr("list", list.clone());
-            if autofocus {
-                text_input = text_input.attr("autofocus", "autofocus")
+            pub fn value(&self) -> &'static str {
+                match self {
+                    Program::Fixed => "fixed",
+                    Program::Linear => "linear",
+                    Program::FiveThreeOne => "five_three_one",
+                }
+            }
+
+            pub fn label(&self) -> &'static str {
+                match self {
+                    Program::Fixed => "fixed",
+                    Program::Linear => "linear progression",
+                    Program::FiveThreeOne => "5/3/1",
+                }
             }
-            (
-                text_input,
-                datalist.id(list)(
-                    options
-                        .into_iter()
-                        .map(|s| option.value(s))
-                        .collect::<Vec<_>>(),
-                ),
-            )
         }
 
-        pub fn profile_part(user: User) -> impl Render {
-            div.class("flex flex-col gap-8 px-4 lg:px-0")((
-                h1.class("text-2xl text-center")("Profile"),
-                h1(("Your secret key: ", span.class("font-bold")(user.secret))),
-                p("don't lose this, it's your only way back to your sets"),
-                form(Route::Logout)(button()("logout")),
-            ))
+        #[derive(Serialize, Deserialize, Clone, Debug)]
+        pub struct TemplateExercise {
+            pub id: String,
+            pub user_id: String,
+            pub template_id: String,
+            pub position: i64,
+            pub name: String,
+            pub sets: usize,
+            pub reps: usize,
+            pub weight: f64,
+            pub created_at: u64,
+            pub increment: f64,
         }
 
-        fn form(route: Route) -> Tag {
-            html::form.method("post").action(route)
+        impl TemplateExercise {
+            pub fn new(template: &Template, position: i64, form: TemplateExerciseForm) -> Self {
+                Self {
+                    id: ulid(),
+                    user_id: template.user_id.clone(),
+                    template_id: template.id.clone(),
+                    position,
+                    name: form.name.trim().to_owned(),
+                    sets: form.sets.max(1),
+                    reps: form.reps,
+                    weight: form.weight,
+                    created_at: now(),
+                    increment: form.increment,
+                }
+            }
         }
 
-        pub fn login_form_part(login_form: LoginForm) -> impl Render {
-            form(Route::Login).class("flex flex-col gap-4 px-4 lg:px-0")((
-                div(match login_form.error {
-                    Some(err) => err,
-                    None => "".into(),
-                }),
-                div.class("flex flex-col gap-1")((
-                    label("Enter your secret"),
-                    text_input()
-                        .attr("autofocus", "autofocus")
-                        .name("secret")
-                        .value(login_form.secret),
-                )),
-                button()("login"),
-            ))
+        #[derive(Serialize)]
+        struct TemplateExercisePosition {
+            position: i64,
         }
-    }
 
-    #[derive(Serialize, Deserialize, Default)]
-    struct SetForm {
-        name: String,
-        reps: usize,
-        weight: usize,
-    }
+        /// A template being worked through on the set form, one exercise
+        /// at a time
+        pub struct Routine {
+            pub template: Template,
+            pub exercises: Vec<TemplateExercise>,
+            pub step: usize,
+            /// sets of the current exercise logged today
+            pub done: usize,
+        }
 
-    #[derive(StaticFiles)]
-    struct StaticFile {
-        #[file("/static/htmx.js")]
-        htmx: Js,
-        #[file("/static/tailwind.css")]
-        tailwind: Css,
-        #[file("/static/json-enc.js")]
-        json_enc: Js,
-        #[file("/static/preload.js")]
-        preload: Js,
-    }
+        impl Routine {
+            pub fn current(&self) -> Option<&TemplateExercise> {
+                self.exercises.get(self.step)
+            }
+        }
 
-    #[derive(Routes, PartialEq, Debug, Clone, Copy)]
-    enum Route {
-        #[get("/")]
-        Root,
-        #[get("/set-form")]
-        SetForm, // logged in
-        #[post("/sets")]
-        CreateSet,
-        #[get("/sets")]
-        SetList,
-        #[get("/profile")]
-        Profile,
-        #[post("/delete-set")]
-        DeleteSet,
-        #[post("/logout")]
-        Logout,
-        #[get("/login")]
-        LoginForm,
-        #[post("/login")]
-        Login,
-    }
+        impl Database {
+            pub async fn templates(&self, user: &User) -> Result<Vec<Template>> {
+                let Self {
+                    ref db, templates, ..
+                } = *self;
+                let rows: Vec<Template> = db
+                    .select()
+                    .from(templates)
+                    .r#where(eq(templates.user_id, &user.id))
+                    .order(vec![asc(templates.name)])
+                    .all()
+                    .await?;
 
-    impl From<Route> for Cow<'static, str> {
-        fn from(value: Route) -> Self {
-            Cow::Owned(value.to_string())
-        }
-    }
+                Ok(rows)
+            }
 
-    #[allow(unused)]
-    #[JustError]
-    pub enum Error {
-        NotFound,
-        Database(String),
-        InternalServer,
-        RowNotFound,
-        UserNotFound,
-    }
+            pub async fn template(
+                &self,
+                user: &User,
+                id: &str,
+            ) -> Result<(Template, Vec<TemplateExercise>)> {
+                let Self {
+                    ref db,
+                    templates,
+                    template_exercises,
+                    ..
+                } = *self;
+                let template: Template = db
+                    .select()
+                    .from(templates)
+                    .r#where(and(eq(templates.id, id), eq(templates.user_id, &user.id)))
+                    .first()
+                    .await?;
+                let exercises: Vec<TemplateExercise> = db
+                    .select()
+                    .from(template_exercises)
+                    .r#where(eq(template_exercises.template_id, &template.id))
+                    .order(vec![asc(template_exercises.position)])
+                    .all()
+                    .await?;
 
-    type Result<T> = std::result::Result<T, Error>;
+                Ok((template, exercises))
+            }
 
-    mod db {
-        use crate::backend::*;
-        use dubs::{rizz, Connection, JournalMode};
-        use dubs::{Integer, Table, Text};
+            pub async fn routine(&self, user: &User, id: &str, step: usize) -> Result<Routine> {
+                let (template, exercises) = self.template(user, id).await?;
+                let step = step.min(exercises.len().saturating_sub(1));
+                let done = match exercises.get(step) {
+                    Some(current) => {
+                        let tz = user.tz();
+                        let today = date::start_of(date::today(tz), tz);
+                        // warm-ups don't count toward the target, same as `program`
+                        self.sets_named(user, &current.name, today)
+                            .await?
+                            .iter()
+                            .filter(|set| set.set_type.counts())
+                            .count()
+                    }
+                    None => 0,
+                };
 
-        #[derive(Clone, Debug)]
-        pub struct Database {
-            pub db: rizz::Database,
-            pub users: Users,
-            pub sessions: Sessions,
-            pub sets: Sets,
-        }
+                Ok(Routine {
+                    template,
+                    exercises,
+                    step,
+                    done,
+                })
+            }
 
-        impl Database {
-            fn new(db: rizz::Database) -> Self {
-                let users = Users::new();
-                let sessions = Sessions::new();
-                let sets = Sets::new();
+            /// Sets of one exercise performed since `since`, oldest first
+            pub async fn sets_named(
+                &self,
+                user: &User,
+                name: &str,
+                since: u64,
+            ) -> Result<Vec<Set>> {
+                let Self { ref db, sets, .. } = *self;
+                let rows: Vec<Set> = db
+                    .select()
+                    .from(sets)
+                    .r#where(and(
+                        and(eq(sets.user_id, &user.id), eq(sets.name, name)),
+                        and(is_null(sets.deleted_at), gte(sets.performed_at, since)),
+                    ))
+                    .order(vec![asc(sets.performed_at)])
+                    .all()
+                    .await?;
 
-                Self {
-                    db,
-                    sets,
-                    sessions,
-                    users,
-                }
+                Ok(rows)
             }
 
-            async fn migrate(&self) -> Result<()> {
+            pub async fn delete_template(&self, user: &User, id: &str) -> Result<()> {
                 let Self {
                     ref db,
-                    users,
-                    sessions,
-                    sets,
+                    templates,
+                    template_exercises,
+                    ..
                 } = *self;
-
+                let (template, _) = self.template(user, id).await?;
                 let _ = db
-                    .create_table(users)
-                    .create_unique_index(users, vec![users.secret])
-                    .create_unique_index(users, vec![users.created_at])
-                    .create_table(sessions)
-                    .create_table(sets)
-                    .migrate()
+                    .delete_from(template_exercises)
+                    .r#where(eq(template_exercises.template_id, &template.id))
+                    .rows_affected()
+                    .await?;
+                let _ = db
+                    .delete_from(templates)
+                    .r#where(eq(templates.id, &template.id))
+                    .rows_affected()
                     .await?;
 
                 Ok(())
             }
-        }
 
-        #[allow(unused)]
-        #[derive(Table, Clone, Copy, Debug)]
-        #[rizz(table = "users")]
-        pub struct Users {
-            #[rizz(primary_key)]
-            pub id: Text,
-            #[rizz(not_null)]
-            pub secret: Text,
-            #[rizz(not_null)]
-            pub created_at: Integer,
-        }
+            /// Returns the template id so the caller can go back to it
+            pub async fn delete_template_exercise(&self, user: &User, id: &str) -> Result<String> {
+                let Self {
+                    ref db,
+                    template_exercises,
+                    ..
+                } = *self;
+                let exercise: TemplateExercise = db
+                    .select()
+                    .from(template_exercises)
+                    .r#where(and(
+                        eq(template_exercises.id, id),
+                        eq(template_exercises.user_id, &user.id),
+                    ))
+                    .first()
+                    .await?;
+                let _ = db
+                    .delete_from(template_exercises)
+                    .r#where(eq(template_exercises.id, &exercise.id))
+                    .rows_affected()
+                    .await?;
 
-        #[allow(unused)]
-        #[derive(Table, Clone, Copy, Debug)]
-        #[rizz(table = "sessions")]
-        pub struct Sessions {
-            #[rizz(primary_key)]
-            pub id: Text,
-            #[rizz(not_null, references = "users(id)")]
-            pub user_id: Text,
-            #[rizz(not_null)]
-            pub created_at: Integer,
+                Ok(exercise.template_id)
+            }
+
+            /// Swaps positions with the neighbouring exercise, returns the
+            /// template id
+            pub async fn move_template_exercise(
+                &self,
+                user: &User,
+                id: &str,
+                up: bool,
+            ) -> Result<String> {
+                let Self {
+                    ref db,
+                    template_exercises,
+                    ..
+                } = *self;
+                let exercise: TemplateExercise = db
+                    .select()
+                    .from(template_exercises)
+                    .r#where(and(
+                        eq(template_exercises.id, id),
+                        eq(template_exercises.user_id, &user.id),
+                    ))
+                    .first()
+                    .await?;
+                let (_, exercises) = self.template(user, &exercise.template_id).await?;
+                let index = exercises
+                    .iter()
+                    .position(|other| other.id == exercise.id)
+                    .ok_or(Error::NotFound)?;
+                let neighbour = match up {
+                    true => index.checked_sub(1).and_then(|index| exercises.get(index)),
+                    false => exercises.get(index + 1),
+                };
+                if let Some(neighbour) = neighbour {
+                    for (id, position) in [
+                        (&exercise.id, neighbour.position),
+                        (&neighbour.id, exercise.position),
+                    ] {
+                        let _ = db
+                            .update(template_exercises)
+                            .set(TemplateExercisePosition { position })?
+                            .r#where(eq(template_exercises.id, id))
+                            .rows_affected()
+                            .await?;
+                    }
+                }
+
+                Ok(exercise.template_id)
+            }
         }
 
-        #[allow(unused)]
-        #[derive(Table, Clone, Copy, Debug)]
-        #[rizz(table = "sets")]
-        pub struct Sets {
-            #[rizz(primary_key)]
-            pub id: Text,
-            #[rizz(not_null, references = "users(id)")]
-            pub user_id: Text,
-            #[rizz(not_null)]
-            pub name: Text,
-            #[rizz(not_null)]
-            pub weight: Integer,
-            #[rizz(not_null)]
-            pub reps: Integer,
-            #[rizz(not_null)]
-            pub created_at: Integer,
+        #[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub enum SetType {
+            WarmUp,
+            #[default]
+            Working,
+            Drop,
+            Failure,
+            Amrap,
         }
 
-        pub async fn db<'a>() -> &'a Database {
-            match DB.get() {
-                Some(db) => db,
-                None => {
-                    let db = Connection::new("db.sqlite3")
-                        .create_if_missing(true)
-                        .journal_mode(JournalMode::Wal)
-                        .foreign_keys(true)
-                        .open()
-                        .await
-                        .expect("Could not connect to database")
-                        .database();
-                    let db = Database::new(db);
-                    let _ = db.migrate().await.expect("Migrations failed");
+        impl SetType {
+            pub const ALL: [SetType; 5] = [
+                SetType::WarmUp,
+                SetType::Working,
+                SetType::Drop,
+                SetType::Failure,
+                SetType::Amrap,
+            ];
 
-                    DB.get_or_init(|| db)
+            /// Matches the serde name, which is what sqlite stores
+            pub fn value(&self) -> &'static str {
+                match self {
+                    SetType::WarmUp => "warm_up",
+                    SetType::Working => "working",
+                    SetType::Drop => "drop",
+                    SetType::Failure => "failure",
+                    SetType::Amrap => "amrap",
                 }
             }
-        }
 
-        impl From<rizz::Error> for Error {
-            fn from(value: rizz::Error) -> Self {
-                match value {
-                    rizz::Error::RowNotFound => Error::NotFound,
-                    rizz::Error::Database(err) => Error::Database(err),
-                    _ => Error::InternalServer,
+            pub fn label(&self) -> &'static str {
+                match self {
+                    SetType::WarmUp => "warm-up",
+                    SetType::Working => "working",
+                    SetType::Drop => "drop set",
+                    SetType::Failure => "to failure",
+                    SetType::Amrap => "amrap",
                 }
             }
+
+            /// Warm-ups are left out of volume and PRs, keep aggregate sql in
+            /// step with `set_type != 'warm_up'`
+            pub fn counts(&self) -> bool {
+                *self != SetType::WarmUp
+            }
         }
 
-        #[derive(Serialize, Deserialize, Debug, Default)]
-        pub struct User {
-            pub id: String,
-            pub secret: String,
-            pub created_at: u64,
+        /// Partial update for soft deleting and restoring a set
+        #[derive(Serialize)]
+        pub struct SetDeletedAt {
+            pub deleted_at: Option<u64>,
         }
-        impl User {
-            pub fn new() -> Self {
+
+        impl Set {
+            pub fn new(user: &User, form: SetForm) -> Self {
+                let created_at = now();
                 Self {
                     id: ulid(),
-                    secret: ulid(),
-                    created_at: now(),
+                    user_id: user.id.clone(),
+                    performed_at: performed_at(user, &form).unwrap_or(created_at),
+                    name: form.name,
+                    weight: form.weight,
+                    reps: form.reps,
+                    created_at,
+                    deleted_at: None,
+                    rpe: form.rpe.filter(|rpe| is_valid_rpe(*rpe)),
+                    rir: form.rir,
+                    notes: form.notes,
+                    set_type: form.set_type,
+                    duration: form.duration,
+                    distance: form.distance,
                 }
             }
+
+            /// Everything but who and when it was logged, a blank performed
+            /// at keeps the old one
+            pub fn edit(&mut self, user: &User, form: SetForm) {
+                self.performed_at = performed_at(user, &form).unwrap_or(self.performed_at);
+                self.name = form.name;
+                self.weight = form.weight;
+                self.reps = form.reps;
+                self.rpe = form.rpe.filter(|rpe| is_valid_rpe(*rpe));
+                self.rir = form.rir;
+                self.notes = form.notes;
+                self.set_type = form.set_type;
+                self.duration = form.duration;
+                self.distance = form.distance;
+            }
         }
 
-        #[derive(Serialize, Deserialize, Debug)]
-        pub struct Session {
-            pub id: String,
-            pub user_id: String,
-            pub created_at: u64,
+        /// Sets can be backdated but not logged ahead
+        fn performed_at(user: &User, form: &SetForm) -> Option<u64> {
+            form.performed_at
+                .as_deref()
+                .and_then(|performed_at| date::parse_time(performed_at, user.tz()))
+                .map(|performed_at| performed_at.min(now()))
         }
 
-        #[derive(Clone, Serialize, Deserialize, Debug)]
-        pub struct Set {
-            pub id: String,
-            pub user_id: String,
-            pub name: String,
-            pub weight: usize,
-            pub reps: usize,
-            pub created_at: u64,
+        fn is_valid_rpe(rpe: f64) -> bool {
+            (6.0..=10.0).contains(&rpe) && (rpe * 2.0).fract() == 0.0
         }
 
-        impl Set {
-            pub fn new(user: &User, name: String, reps: usize, weight: usize) -> Self {
+        impl Session {
+            pub fn new(user: &User) -> Self {
                 Self {
                     id: ulid(),
                     user_id: user.id.clone(),
-                    name,
-                    weight,
-                    reps,
                     created_at: now(),
                 }
             }
         }
+    }
 
-        impl Session {
-            pub fn new(user: &User) -> Self {
-                Self {
-                    id: ulid(),
-                    user_id: user.id.clone(),
-                    created_at: now(),
+    mod cli {
+        use super::*;
+
+        pub const USAGE: &str = "usage: uliftbro [--db <path>] [command]
+
+commands:
+  serve            the default, [--addr <ip:port>] [--backup-dir <dir>]
+                   [--backup-every <hours>] [--backup-keep <count>]
+  migrate          apply pending migrations, [--dry-run] only lists them
+  backup <file>    copy the live database to <file>
+  restore <file>   replace the database with <file>, stop the server first
+  list-users       id, created at and set count of every user
+  delete-user <id> remove a user with their sessions and sets
+  prune-sessions   remove sessions, [--older-than <days>] defaults to 400
+  stats            row counts and database size
+
+the database defaults to $DATABASE_PATH or db.sqlite3";
+
+        pub struct Cli {
+            path: String,
+            command: Command,
+        }
+
+        enum Command {
+            Serve {
+                addr: String,
+                backups: Option<(String, u64, usize)>,
+            },
+            Migrate {
+                dry_run: bool,
+            },
+            Backup(String),
+            Restore(String),
+            ListUsers,
+            DeleteUser(String),
+            PruneSessions(u64),
+            Stats,
+        }
+
+        pub fn parse(args: &[String]) -> std::result::Result<Cli, String> {
+            let path = flag(args, "--db")
+                .map(|path| path.to_owned())
+                .or_else(|| std::env::var("DATABASE_PATH").ok())
+                .unwrap_or_else(|| "db.sqlite3".into());
+            let has = |name: &str| args.iter().any(|arg| arg == name);
+            let positional = positional(args);
+            let number = |name: &str, default| match flag(args, name) {
+                Some(value) => value
+                    .parse()
+                    .map_err(|_| format!("{} expects a number, got {}", name, value)),
+                None => Ok(default),
+            };
+            let argument = |name: &str| {
+                positional
+                    .get(1)
+                    .map(|arg| arg.to_string())
+                    .ok_or_else(|| format!("{} is missing its argument", name))
+            };
+
+            let command = match positional.first().copied() {
+                None | Some("serve") if has("--migrate-only") => Command::Migrate {
+                    dry_run: has("--dry-run"),
+                },
+                None | Some("serve") => Command::Serve {
+                    addr: flag(args, "--addr").unwrap_or("127.0.0.1:9005").to_owned(),
+                    backups: match flag(args, "--backup-dir") {
+                        Some(dir) => Some((
+                            dir.to_owned(),
+                            // tokio's interval panics on a zero period
+                            match number("--backup-every", 24)? {
+                                0 => return Err("--backup-every must be at least 1".to_owned()),
+                                hours => hours,
+                            },
+                            number("--backup-keep", 7)? as usize,
+                        )),
+                        None => None,
+                    },
+                },
+                Some("migrate") => Command::Migrate {
+                    dry_run: has("--dry-run"),
+                },
+                Some("backup") => Command::Backup(argument("backup")?),
+                Some("restore") => Command::Restore(argument("restore")?),
+                Some("list-users") => Command::ListUsers,
+                Some("delete-user") => Command::DeleteUser(argument("delete-user")?),
+                Some("prune-sessions") => Command::PruneSessions(number("--older-than", 400)?),
+                Some("stats") => Command::Stats,
+                Some(command) => return Err(format!("unknown command {}", command)),
+            };
+
+            Ok(Cli { path, command })
+        }
+
+        /// Everything that isn't a flag or a flag's value
+        fn positional(args: &[String]) -> Vec<&str> {
+            const VALUE_FLAGS: [&str; 6] = [
+                "--db",
+                "--addr",
+                "--backup-dir",
+                "--backup-every",
+                "--backup-keep",
+                "--older-than",
+            ];
+            let mut positional = vec![];
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                if VALUE_FLAGS.contains(&arg.as_str()) {
+                    args.next();
+                } else if !arg.starts_with("--") {
+                    positional.push(arg.as_str());
+                }
+            }
+            positional
+        }
+
+        /// The value after `name`, as in `--backup-dir backups`
+        fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+            args.iter()
+                .position(|arg| arg == name)
+                .and_then(|i| args.get(i + 1))
+                .map(|value| value.as_str())
+        }
+
+        pub async fn run(Cli { path, command }: Cli) -> Result<()> {
+            match command {
+                Command::Serve { addr, backups } => {
+                    let db = Database::connect(&path).await;
+                    for migration in db.migrate().await? {
+                        println!("applied {:04} {}", migration.version, migration.name);
+                    }
+                    tokio::spawn(purge_deleted_sets(db.clone()));
+                    if let Some((dir, hours, keep)) = backups {
+                        tokio::spawn(backup::schedule(db.clone(), dir, hours, keep));
+                    }
+                    app(db).serve(&addr).await;
+                }
+                Command::Migrate { dry_run: true } => {
+                    let db = Database::connect(&path).await;
+                    for migration in db.pending_migrations().await? {
+                        println!("pending {:04} {}", migration.version, migration.name);
+                    }
+                }
+                Command::Migrate { dry_run: false } => {
+                    let db = Database::connect(&path).await;
+                    for migration in db.migrate().await? {
+                        println!("applied {:04} {}", migration.version, migration.name);
+                    }
+                }
+                Command::Backup(to) => {
+                    let db = Database::open(&path).await?;
+                    backup::backup(&db, &to).await?;
+                    println!("backed up {} to {}", path, to);
+                }
+                Command::Restore(from) => {
+                    backup::restore(&from, &path).await?;
+                    println!("restored {} from {}", path, from);
+                }
+                Command::ListUsers => {
+                    let db = Database::open(&path).await?;
+                    for user in db.user_summaries().await? {
+                        println!("{}\t{}\t{} sets", user.id, user.created_at, user.sets);
+                    }
+                }
+                Command::DeleteUser(id) => {
+                    let db = Database::open(&path).await?;
+                    db.delete_user(&id).await?;
+                    println!("deleted user {}", id);
+                }
+                Command::PruneSessions(days) => {
+                    let db = Database::open(&path).await?;
+                    let pruned = db
+                        .prune_sessions(now().saturating_sub(days.saturating_mul(86_400)))
+                        .await?;
+                    println!("pruned {} sessions older than {} days", pruned, days);
+                }
+                Command::Stats => {
+                    let db = Database::open(&path).await?;
+                    let stats = db.stats().await?;
+                    let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    println!("schema version\t{}", db.schema_version().await?);
+                    println!("users\t{}", stats.users);
+                    println!("sessions\t{}", stats.sessions);
+                    println!("sets\t{}", stats.sets);
+                    println!("working sets\t{}", stats.working_sets);
+                    println!("volume\t{} lbs", stats.volume);
+                    println!("size\t{} bytes", bytes);
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    mod backup {
+        use super::*;
+        use db::SCHEMA_VERSION;
+        use std::path::Path;
+        use std::time::Duration;
+
+        const PREFIX: &str = "uliftbro-";
+        const EXTENSION: &str = ".sqlite3";
+
+        /// A consistent copy of a live WAL database, safe to run while the
+        /// server is writing
+        pub async fn backup(db: &Database, to: &str) -> Result<()> {
+            if Path::new(to).exists() {
+                return Err(Error::Backup(format!("{} already exists", to)));
+            }
+            let _ = db
+                .db
+                .execute(&format!("vacuum into '{}'", to.replace('\'', "''")))
+                .await?;
+
+            Ok(())
+        }
+
+        /// Replaces the database at `to` with `from` once `from` passes an
+        /// integrity check. Stop the server first.
+        pub async fn restore(from: &str, to: &str) -> Result<()> {
+            if !Path::new(from).exists() {
+                return Err(Error::Backup(format!("{} does not exist", from)));
+            }
+            check(from).await?;
+
+            let tmp = format!("{}.restore", to);
+            let copied = std::fs::copy(from, &tmp).and_then(|_| {
+                for suffix in ["-wal", "-shm"] {
+                    let _ = std::fs::remove_file(format!("{}{}", to, suffix));
+                }
+                std::fs::rename(&tmp, to)
+            });
+            if let Err(err) = copied {
+                let _ = std::fs::remove_file(&tmp);
+                return Err(Error::Backup(err.to_string()));
+            }
+
+            Ok(())
+        }
+
+        #[derive(Deserialize)]
+        struct IntegrityCheck {
+            integrity_check: String,
+        }
+
+        async fn check(path: &str) -> Result<()> {
+            let db = Database::read_only(path).await?;
+            let rows: Vec<IntegrityCheck> = db.db.query("pragma integrity_check").all().await?;
+            let errors = rows
+                .into_iter()
+                .map(|row| row.integrity_check)
+                .filter(|row| row != "ok")
+                .collect::<Vec<_>>();
+            if !errors.is_empty() {
+                return Err(Error::Backup(errors.join("\n")));
+            }
+            let version = db.schema_version().await?;
+            if version > SCHEMA_VERSION {
+                return Err(Error::DatabaseTooNew(version, SCHEMA_VERSION));
+            }
+
+            Ok(())
+        }
+
+        /// Backs up into `dir` every `hours`, keeping the newest `keep` files
+        pub async fn schedule(db: Database, dir: String, hours: u64, keep: usize) {
+            if let Err(err) = std::fs::create_dir_all(&dir) {
+                eprintln!("backups disabled, could not create {}: {}", dir, err);
+                return;
+            }
+            let mut interval = tokio::time::interval(Duration::from_secs(hours * 3600));
+            loop {
+                interval.tick().await;
+                let to = Path::new(&dir).join(format!("{}{}{}", PREFIX, now(), EXTENSION));
+                let to = to.to_string_lossy();
+                match backup(&db, &to).await.and_then(|_| prune(&dir, keep)) {
+                    Ok(()) => println!("backed up to {}", to),
+                    Err(err) => eprintln!("backup failed: {}", err),
                 }
             }
         }
+
+        fn prune(dir: &str, keep: usize) -> Result<()> {
+            let mut backups = std::fs::read_dir(dir)
+                .map_err(|err| Error::Backup(err.to_string()))?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .map(|name| name.starts_with(PREFIX) && name.ends_with(EXTENSION))
+                        .unwrap_or(false)
+                })
+                .collect::<Vec<_>>();
+            // timestamps are seconds, so names sort oldest first
+            backups.sort();
+            let excess = backups.len().saturating_sub(keep);
+            for path in backups.into_iter().take(excess) {
+                std::fs::remove_file(path).map_err(|err| Error::Backup(err.to_string()))?;
+            }
+
+            Ok(())
+        }
     }
 
-    static DB: std::sync::OnceLock<db::Database> = std::sync::OnceLock::new();
+    #[async_trait]
+    impl<S> FromRequestParts<S> for Database
+    where
+        Database: FromRef<S>,
+        S: Send + Sync,
+    {
+        type Rejection = std::convert::Infallible;
+
+        async fn from_request_parts(
+            _parts: &mut Parts,
+            state: &S,
+        ) -> std::result::Result<Self, Self::Rejection> {
+            Ok(Database::from_ref(state))
+        }
+    }
 
     #[async_trait]
     impl<S> FromRequestParts<S> for User
     where
+        Database: FromRef<S>,
         S: Send + Sync,
     {
         type Rejection = Error;
@@ -773,7 +6047,7 @@ mod backend {
                 users,
                 sessions,
                 ..
-            } = db().await;
+            } = Database::from_ref(state);
             let session: Session = db
                 .select()
                 .from(sessions)
@@ -796,6 +6070,7 @@ mod backend {
     #[async_trait]
     impl<S> FromRequestParts<S> for SomeUser
     where
+        Database: FromRef<S>,
         S: Send + Sync,
     {
         type Rejection = Error;
@@ -812,25 +6087,43 @@ mod backend {
         }
     }
 
-    fn not_found(error: Error) -> Response {
-        (StatusCode::NOT_FOUND, error.to_string()).into_response()
+    fn error_page(status_code: StatusCode) -> Response {
+        error_response(
+            status_code,
+            status_code
+                .canonical_reason()
+                .unwrap_or("something went wrong"),
+        )
+    }
+
+    fn error_response(status_code: StatusCode, message: impl Render) -> Response {
+        response(Route::Root, error_part(status_code, message))
+            .status(status_code)
+            .header(CACHE_CONTROL, HeaderValue::from_static("no-store"))
+            .into_response()
+    }
+
+    fn not_found(_error: Error) -> Response {
+        error_page(StatusCode::NOT_FOUND)
     }
 
     fn internal_server_error(error: Error) -> Response {
         #[cfg(debug_assertions)]
-        return (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response();
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
         #[cfg(not(debug_assertions))]
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "internal server error".to_owned(),
-        )
-            .into_response();
+        return {
+            let _ = error;
+            error_page(StatusCode::INTERNAL_SERVER_ERROR)
+        };
     }
 
     impl IntoResponse for Error {
         fn into_response(self) -> Response {
             match self {
                 Error::NotFound | Error::RowNotFound => not_found(self),
+                Error::Invalid(message) => {
+                    error_response(StatusCode::UNPROCESSABLE_ENTITY, message)
+                }
                 // Error::UserNotFound => render_login()Responder::default()
                 //     .render(Login(0, Login::default(), "login failed. secret incorrect"))
                 //     .into_response(),
@@ -864,4 +6157,252 @@ mod backend {
         .join(";");
         HeaderValue::from_str(&format!("{}", parts)).unwrap()
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use dubs::test::{TempDatabase, TestClient};
+
+        async fn client() -> (TempDatabase, Database, TestClient) {
+            let temp = TempDatabase::new();
+            let database = Database::open(temp.path()).await.unwrap();
+            let client = TestClient::new(app(database.clone()).router());
+
+            (temp, database, client)
+        }
+
+        fn squat() -> SetForm {
+            SetForm {
+                name: "squat".into(),
+                reps: 5,
+                weight: 225.0,
+                ..Default::default()
+            }
+        }
+
+        #[tokio::test]
+        async fn create_set_signs_up_and_lists_the_set() {
+            let (_temp, _database, mut client) = client().await;
+
+            client
+                .post(&Route::CreateSet.to_string(), squat())
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_redirected_to(&Route::SetList.to_string())
+                .assert_contains("squat")
+                .assert_contains("this is your secret");
+            assert!(client.cookie("id").is_some());
+
+            client
+                .get(&Route::SetList.to_string())
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_contains("squat");
+        }
+
+        #[tokio::test]
+        async fn login_with_a_secret_starts_a_session() {
+            let (_temp, database, mut client) = client().await;
+            let Database { ref db, users, .. } = database;
+            let user: User = db
+                .insert(users)
+                .values(User::new())
+                .unwrap()
+                .returning()
+                .await
+                .unwrap();
+
+            client
+                .post(
+                    &Route::Login.to_string(),
+                    LoginForm {
+                        secret: "not it".into(),
+                        error: None,
+                    },
+                )
+                .await
+                .assert_status(StatusCode::OK);
+            assert!(client.cookie("id").is_none());
+
+            client
+                .post(
+                    &Route::Login.to_string(),
+                    LoginForm {
+                        secret: user.secret,
+                        error: None,
+                    },
+                )
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_redirected_to(&Route::SetForm.to_string());
+            assert!(client.cookie("id").is_some());
+        }
+
+        #[tokio::test]
+        async fn delete_account_removes_everything_the_user_owns() {
+            let (_temp, database, mut client) = client().await;
+            client.post(&Route::CreateSet.to_string(), squat()).await;
+
+            client
+                .post(
+                    &Route::DeleteAccount.to_string(),
+                    DeleteAccountForm {
+                        confirm: "delete".into(),
+                        error: None,
+                    },
+                )
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_redirected_to(&Route::Root.to_string());
+
+            let stats = database.stats().await.unwrap();
+            assert_eq!((stats.users, stats.sessions, stats.sets), (0, 0, 0));
+        }
+
+        #[tokio::test]
+        async fn export_leaves_out_what_logs_you_in() {
+            let (_temp, _database, mut client) = client().await;
+            client.post(&Route::CreateSet.to_string(), squat()).await;
+            let session_id = client.cookie("id").expect("no session").to_owned();
+
+            client
+                .get(&Route::Export.to_string())
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_contains("\"sessions\":[{\"created_at\"")
+                .assert_not_contains(&session_id)
+                .assert_not_contains("\"secret\"");
+        }
+
+        #[tokio::test]
+        async fn update_time_zone_rejects_an_unknown_zone() {
+            let (_temp, _database, mut client) = client().await;
+            client.post(&Route::CreateSet.to_string(), squat()).await;
+
+            client
+                .post(
+                    &Route::UpdateTimeZone.to_string(),
+                    TimeZoneForm {
+                        time_zone: "Mars/Olympus".into(),
+                    },
+                )
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_contains("Mars/Olympus is not a known time zone");
+        }
+
+        #[tokio::test]
+        async fn search_sets_treats_wildcards_literally() {
+            let (_temp, _database, mut client) = client().await;
+            let response = client.post(&Route::CreateSet.to_string(), squat()).await;
+            let id = response
+                .input_value("id")
+                .expect("no delete form for the set");
+            let search = |query: &str| format!("{}?{}", Route::SetList, query);
+
+            client
+                .get(&search("name=qua"))
+                .await
+                .assert_contains(&format!("set-{}", id));
+            client
+                .get(&search("name=%25"))
+                .await
+                .assert_not_contains(&format!("set-{}", id));
+            client
+                .get(&search("name=s_uat"))
+                .await
+                .assert_not_contains(&format!("set-{}", id));
+            client
+                .get(&search("min_weight=142.5"))
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_contains(&format!("set-{}", id));
+        }
+
+        #[tokio::test]
+        async fn routine_leaves_warm_ups_out_of_the_target() {
+            let (_temp, _database, mut client) = client().await;
+            // signs up with another exercise so only the warm-up is today's squat
+            let bench = SetForm {
+                name: "bench".into(),
+                ..squat()
+            };
+            client.post(&Route::CreateSet.to_string(), bench).await;
+            let response = client
+                .post(
+                    &Route::CreateTemplate.to_string(),
+                    TemplateForm { name: "a".into() },
+                )
+                .await;
+            let template_id = response
+                .redirects
+                .last()
+                .and_then(|redirect| redirect.split_once("id="))
+                .map(|(_, id)| id.to_owned())
+                .expect("no redirect to the template");
+            client
+                .post(
+                    &Route::CreateTemplateExercise.to_string(),
+                    TemplateExerciseForm {
+                        template_id: template_id.clone(),
+                        name: "squat".into(),
+                        sets: 3,
+                        reps: 5,
+                        weight: 225.0,
+                        increment: 5.0,
+                    },
+                )
+                .await;
+
+            client
+                .post(
+                    &Route::CreateSet.to_string(),
+                    SetForm {
+                        set_type: SetType::WarmUp,
+                        template: Some(template_id.clone()),
+                        ..squat()
+                    },
+                )
+                .await;
+
+            client
+                .get(&routine_url(&template_id, 0))
+                .await
+                .assert_contains("0 of 3 sets done");
+        }
+
+        #[tokio::test]
+        async fn delete_set_hides_it_behind_an_undo() {
+            let (_temp, _database, mut client) = client().await;
+            let response = client.post(&Route::CreateSet.to_string(), squat()).await;
+            let id = response
+                .input_value("id")
+                .expect("no delete form for the set");
+
+            let response = client
+                .post(
+                    &Route::DeleteSet.to_string(),
+                    DeleteSetForm { id: id.clone() },
+                )
+                .await;
+            response
+                .assert_status(StatusCode::OK)
+                .assert_redirected_to(&format!("{}?undo={}", Route::SetList, id))
+                .assert_not_contains(&format!("set-{}", id))
+                .assert_contains("Undo");
+            assert_eq!(
+                response.header("hx-replace-url"),
+                Some(Route::SetList.to_string().as_str())
+            );
+
+            client
+                .post(
+                    &Route::UndoDeleteSet.to_string(),
+                    DeleteSetForm { id: id.clone() },
+                )
+                .await
+                .assert_status(StatusCode::OK)
+                .assert_contains(&format!("set-{}", id));
+        }
+    }
 }
@@ -5,11 +5,18 @@ htmx.defineExtension('json-enc', {
         }
     },
     
-    encodeParameters : function(xhr, parameters, _elt) {
+    encodeParameters : function(xhr, parameters, elt) {
         xhr.overrideMimeType('text/json');
+        // only inputs the server marks with data-number are sent as numbers,
+        // notes or names that happen to look numeric stay strings
+        let root = elt.closest('form') || elt;
+        let numbers = new Set(Array.from(root.querySelectorAll('[data-number]'), (input) => input.name));
+        if (root.matches('[data-number]')) {
+            numbers.add(root.name);
+        }
         let entries = Object.keys(parameters).map((k) => {
-            if(isNumber(parameters[k])) {
-                return [k, parseInt(parameters[k], 10)];
+            if(numbers.has(k) && isNumber(parameters[k])) {
+                return [k, Number(parameters[k])];
             } else {
                 return [k, parameters[k]];
             }
@@ -20,5 +27,5 @@ htmx.defineExtension('json-enc', {
 });
 
 function isNumber(n) {
-  return !isNaN(parseInt(n, 10)) && isFinite(n);
+  return n !== "" && !isNaN(parseFloat(n)) && isFinite(n);
 }
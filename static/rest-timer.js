@@ -0,0 +1,79 @@
+// Ticks every [data-rest-since] element up from the unix time it holds and
+// cues once when it passes [data-rest-target] seconds. The cue is opt in per
+// device with a [data-rest-cue] button and remembered in localStorage.
+(function () {
+	var KEY = "rest-cue";
+	var audio;
+
+	function format(seconds) {
+		var minutes = Math.floor(seconds / 60);
+		var rest = seconds % 60;
+		return minutes + ":" + (rest < 10 ? "0" : "") + rest;
+	}
+
+	function cueOn() {
+		return localStorage.getItem(KEY) === "on";
+	}
+
+	function cue() {
+		if (!cueOn()) {
+			return;
+		}
+		if (navigator.vibrate) {
+			navigator.vibrate([200, 100, 200]);
+		}
+		if (audio) {
+			var oscillator = audio.createOscillator();
+			oscillator.frequency.value = 880;
+			oscillator.connect(audio.destination);
+			oscillator.start();
+			oscillator.stop(audio.currentTime + 0.3);
+		}
+	}
+
+	function tick() {
+		var now = Math.floor(Date.now() / 1000);
+		document.querySelectorAll("[data-rest-since]").forEach(function (timer) {
+			var elapsed = Math.max(0, now - parseInt(timer.dataset.restSince, 10));
+			var target = parseInt(timer.dataset.restTarget || "0", 10);
+			var output = timer.querySelector("[data-rest-elapsed]");
+			if (output) {
+				output.textContent = format(elapsed);
+			}
+			if (target <= 0) {
+				return;
+			}
+			// only cue when the target passes while the page is open
+			if (timer.dataset.restDone === undefined) {
+				timer.dataset.restDone = elapsed >= target ? "1" : "";
+			}
+			if (elapsed >= target) {
+				timer.classList.add("text-orange-500");
+				if (!timer.dataset.restDone) {
+					timer.dataset.restDone = "1";
+					cue();
+				}
+			}
+		});
+		document.querySelectorAll("[data-rest-cue]").forEach(function (button) {
+			button.textContent = cueOn() ? "cue on" : "cue off";
+		});
+	}
+
+	document.addEventListener("click", function (event) {
+		var button = event.target.closest("[data-rest-cue]");
+		if (!button) {
+			return;
+		}
+		event.preventDefault();
+		localStorage.setItem(KEY, cueOn() ? "off" : "on");
+		// browsers only allow audio after a tap, so set it up here
+		if (cueOn() && !audio && (window.AudioContext || window.webkitAudioContext)) {
+			audio = new (window.AudioContext || window.webkitAudioContext)();
+		}
+		tick();
+	});
+	document.addEventListener("DOMContentLoaded", tick);
+	document.addEventListener("htmx:load", tick);
+	setInterval(tick, 1000);
+})();
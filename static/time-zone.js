@@ -0,0 +1,21 @@
+// Fills every [data-time-zone] input with the browser's IANA time zone so a
+// new account starts out on the user's local calendar.
+(function () {
+	function fill() {
+		var zone;
+		try {
+			zone = Intl.DateTimeFormat().resolvedOptions().timeZone;
+		} catch (e) {
+			return;
+		}
+		if (!zone) {
+			return;
+		}
+		document.querySelectorAll("input[data-time-zone]").forEach(function (input) {
+			input.value = zone;
+		});
+	}
+
+	document.addEventListener("DOMContentLoaded", fill);
+	document.addEventListener("htmx:load", fill);
+})();